@@ -0,0 +1,11 @@
+mod parser;
+mod renderer;
+mod utility;
+
+pub use parser::{
+    author_name_from_cargo_pkg_authors, convert_str,
+    error::{ParseError, ParseErrorKind, SourceError},
+    parse_mdx_file, ConvertOptions, FootnoteOutput, ImageOutput, Locale, Typography,
+    TypographyRule, DEFAULT_CALLOUT_COMPONENT, DEFAULT_UNITS,
+};
+pub use renderer::AstroOutput;
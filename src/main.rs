@@ -1,6 +1,3 @@
-mod parser;
-mod utility;
-
 use clap::Parser;
 use notify::RecursiveMode;
 use notify_debouncer_mini::{new_debouncer, DebouncedEvent};
@@ -9,16 +6,23 @@ use std::{
     fs,
     io::{self, BufRead, IsTerminal, Write},
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use parser::{author_name_from_cargo_pkg_authors, parse_mdx_file};
+use cmessless::{
+    author_name_from_cargo_pkg_authors, parse_mdx_file, FootnoteOutput, ImageOutput, Locale,
+    ParseError, Typography, TypographyRule, DEFAULT_CALLOUT_COMPONENT, DEFAULT_UNITS,
+};
 
 #[derive(Parser)]
 #[clap(author,version,about,long_about=None)]
 struct Cli {
     path: Vec<PathBuf>,
 
+    // import local Markdown images from astro:assets, instead of the post-images collection
+    #[clap(long)]
+    astro_assets: bool,
+
     #[clap(short, long)]
     check: bool,
 
@@ -28,6 +32,32 @@ struct Cli {
     #[clap(short = 'R', long)]
     relative: bool, // path should only contain UTF-8 characters
 
+    // output footnotes as Sidenote components beside their references, instead of a footnotes section
+    #[clap(long)]
+    sidenotes: bool,
+
+    // every typography rule for body text, with the default units
+    #[clap(long)]
+    smart_typography: bool,
+
+    /* typography rules for body text, from quotes, dashes, ellipses, widows, units, dimensions and
+     * ranges, and spacing for French punctuation
+     */
+    #[clap(long, value_delimiter = ',')]
+    typography: Vec<TypographyRule>,
+
+    // module the Callout component, for GitHub-style callouts, is imported from
+    #[clap(long, default_value = DEFAULT_CALLOUT_COMPONENT)]
+    callout_component: String,
+
+    // units the units rule keeps with a preceding number, in place of the defaults
+    #[clap(long, value_delimiter = ',')]
+    units: Vec<String>,
+
+    // language, such as fr or de, of posts without a lang frontmatter field
+    #[clap(long)]
+    lang: Option<Locale>,
+
     #[clap(short, long)]
     verbose: bool,
 
@@ -63,6 +93,52 @@ fn print_long_banner() {
     println!("       {} --watch <somefile>.mdx", env!("CARGO_PKG_NAME"));
 }
 
+/***
+ * print a parse error, including the offending source snippet, without stopping the watcher
+ */
+fn report_parse_error(result: Result<(), ParseError>) {
+    if let Err(error) = result {
+        eprintln!("{:?}", miette::Report::new(error));
+    }
+}
+
+// parses an MDX file to Astro markup, echoing the markup when verbose
+fn convert_file<P1: AsRef<Path>, P2: AsRef<Path>>(
+    input_path: &P1,
+    output_path: &P2,
+    verbose: bool,
+    images: ImageOutput,
+    footnotes: FootnoteOutput,
+    typography: &Typography,
+    callout_component: &str,
+) -> Result<(), ParseError> {
+    println!(
+        "[ INFO ] Parsing {:?}...",
+        input_path.as_ref().display().to_string()
+    );
+    let start = Instant::now();
+
+    let output = parse_mdx_file(
+        input_path,
+        output_path,
+        images,
+        footnotes,
+        typography,
+        callout_component,
+    )?;
+
+    let duration = start.elapsed();
+    let duration_milliseconds = duration.as_millis();
+    let duration_microseconds = duration.as_micros() - (duration_milliseconds * 1000);
+    let file_size = fs::metadata(input_path).map_or(0, |metadata| metadata.len()) / 1000;
+    println!("[ INFO ] Parsing complete ({file_size} KB) in {duration_milliseconds}.{duration_microseconds:0>3} ms.");
+    if verbose {
+        println!("{output}");
+        println!("\n");
+    }
+    Ok(())
+}
+
 /***
  * watch a single file for changes and parse to output_path, when changes occur
  */
@@ -70,6 +146,10 @@ async fn debounce_watch<P1: AsRef<Path>, P2: AsRef<Path>>(
     mdx_path: &P1,
     output_path: &P2,
     verbose: bool,
+    images: ImageOutput,
+    footnotes: FootnoteOutput,
+    typography: &Typography,
+    callout_component: &str,
 ) {
     let (tx, rx) = std::sync::mpsc::channel();
 
@@ -84,7 +164,15 @@ async fn debounce_watch<P1: AsRef<Path>, P2: AsRef<Path>>(
         match events {
             // could add a check to make sure the paths match
             Ok(_) => {
-                parse_mdx_file(&mdx_path, output_path, verbose);
+                report_parse_error(convert_file(
+                    &mdx_path,
+                    output_path,
+                    verbose,
+                    images,
+                    footnotes,
+                    typography,
+                    callout_component,
+                ));
             }
             Err(e) => eprintln!("Something went wrong: {e:?}"),
         }
@@ -159,6 +247,10 @@ async fn debounce_watch_multiple<P1: AsRef<Path>, P2: AsRef<Path>>(
     mdx_paths: &[P1],
     output_path_root: &P2,
     verbose: bool,
+    images: ImageOutput,
+    footnotes: FootnoteOutput,
+    typography: &Typography,
+    callout_component: &str,
 ) {
     let (tx, rx) = std::sync::mpsc::channel();
 
@@ -191,13 +283,29 @@ async fn debounce_watch_multiple<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         let path_as_string = path.to_str().unwrap();
                         if let Some(value) = output_paths_map.get(path_as_string) {
-                            parse_mdx_file(path, &value, verbose);
+                            report_parse_error(convert_file(
+                                path,
+                                &value,
+                                verbose,
+                                images,
+                                footnotes,
+                                typography,
+                                callout_component,
+                            ));
                         } else {
                             let output_path_result = output_path_from_relative_input(
                                 &output_path_root,
                                 &mdx_paths[index],
                             );
-                            parse_mdx_file(path, &output_path_result, verbose);
+                            report_parse_error(convert_file(
+                                path,
+                                &output_path_result,
+                                verbose,
+                                images,
+                                footnotes,
+                                typography,
+                                callout_component,
+                            ));
                             output_paths_map
                                 .insert((path_as_string).to_string(), output_path_result);
                         }
@@ -246,7 +354,7 @@ fn get_piped_input() -> Vec<PathBuf> {
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> miette::Result<()> {
     let cli = &Cli::parse();
 
     let inputs = if io::stdin().is_terminal() {
@@ -291,11 +399,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    let images = if cli.astro_assets {
+        ImageOutput::AstroAssets
+    } else {
+        ImageOutput::PostImages
+    };
+    let footnotes = if cli.sidenotes {
+        FootnoteOutput::Sidenotes
+    } else {
+        FootnoteOutput::Section
+    };
+    let mut typography = if cli.smart_typography {
+        Typography::smart()
+    } else {
+        Typography {
+            rules: cli.typography.clone(),
+            units: DEFAULT_UNITS.iter().map(|unit| unit.to_string()).collect(),
+            locale: Locale::default(),
+        }
+    };
+    if !cli.units.is_empty() {
+        typography.units = cli.units.clone();
+    }
+    if let Some(locale) = cli.lang {
+        typography.locale = locale;
+    }
+
     if cli.watch {
         if cli.path.len() == 1 && !cli.relative {
-            debounce_watch(&inputs[0], &cli.output, cli.verbose).await;
+            debounce_watch(
+                &inputs[0],
+                &cli.output,
+                cli.verbose,
+                images,
+                footnotes,
+                &typography,
+                &cli.callout_component,
+            )
+            .await;
         } else {
-            debounce_watch_multiple(&inputs, &cli.output, cli.verbose).await;
+            debounce_watch_multiple(
+                &inputs,
+                &cli.output,
+                cli.verbose,
+                images,
+                footnotes,
+                &typography,
+                &cli.callout_component,
+            )
+            .await;
         }
         return Ok(());
     }
@@ -303,10 +455,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     if cli.relative {
         for val in &inputs {
             let absolute_output_path = output_path_from_relative_input(&cli.output, val);
-            parse_mdx_file(val, &absolute_output_path, cli.verbose);
+            convert_file(
+                val,
+                &absolute_output_path,
+                cli.verbose,
+                images,
+                footnotes,
+                &typography,
+                &cli.callout_component,
+            )?;
         }
     } else {
-        parse_mdx_file(&inputs[0], &cli.output, cli.verbose);
+        convert_file(
+            &inputs[0],
+            &cli.output,
+            cli.verbose,
+            images,
+            footnotes,
+            &typography,
+            &cli.callout_component,
+        )?;
     }
 
     Ok(())
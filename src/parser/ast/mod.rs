@@ -0,0 +1,433 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{parser::jsx::JSXComponentType, utility::stack::Stack};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HTMLBlockElementType {
+    BlockQuote,
+    Comment,
+    DescriptionList,
+    Div,
+    Figure,
+    TableBody,
+    TableHead,
+}
+
+/// GitHub-style alert, introduced by a `> [!NOTE]` style first line in a blockquote
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CalloutType {
+    Caution,
+    Important,
+    Note,
+    Tip,
+    Warning,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListType {
+    Ordered,
+    Unordered,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableAlign {
+    Centre,
+    Left,
+    Right,
+}
+
+/// Parsed MDX document, ready for rendering
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+
+    /// JavaScript declarations components need in the page script (e.g. HowTo structured data)
+    pub declarations: Vec<String>,
+
+    /// footnotes for the section at the end of the page, numbered in order of first reference
+    pub footnotes: Vec<Footnote>,
+
+    /// fields from the YAML frontmatter, in source order
+    pub frontmatter: Vec<(String, FrontmatterValue)>,
+
+    /// page outline, with every heading in document order
+    pub headings: Vec<DocumentHeading>,
+
+    /// local image files imported through `astro:assets`, in import order
+    pub image_assets: Vec<String>,
+}
+
+/// Heading in the page outline, matching the shape of Astro's `MarkdownHeading`
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentHeading {
+    pub depth: usize,
+    pub slug: String,
+
+    /// plain text of the heading, without markup
+    pub text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrontmatterValue {
+    Boolean(bool),
+    Integer(i64),
+    List(Vec<FrontmatterValue>),
+    Map(Vec<(String, FrontmatterValue)>),
+    Null,
+
+    /// kept as written, so no precision is lost
+    Real(String),
+    String(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    BlockQuote(BlockQuote),
+    CodeBlock(CodeBlock),
+
+    /// only found while parsing, definitions are moved to `Document::footnotes` once referenced
+    FootnoteDefinition(FootnoteDefinition),
+    Heading(Heading),
+    HtmlBlock(HtmlBlock),
+    JsxComponent(JsxComponent),
+    List(List),
+
+    /// raw HTML or JSX line, passed through untouched
+    Markup(String),
+    Paragraph(Vec<Inline>),
+    Table(Table),
+
+    /// `---`, `***` or `___` line, rendered as `<hr />`
+    ThematicBreak,
+}
+
+impl Block {
+    // adds a child to a container block
+    fn push_child(&mut self, child: Block) {
+        match self {
+            Block::CodeBlock(code_block) => match child {
+                Block::Markup(line) => code_block.lines.push(line),
+                _ => unreachable!("Code blocks only contain lines of code"),
+            },
+            Block::BlockQuote(BlockQuote { children, .. })
+            | Block::FootnoteDefinition(FootnoteDefinition { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. })
+            | Block::JsxComponent(JsxComponent { children, .. }) => children.push(child),
+            Block::List(list) => match list.items.last_mut() {
+                Some(item) => item.children.push(child),
+                None => list.items.push(ListItem {
+                    checked: None,
+                    children: vec![child],
+                }),
+            },
+            Block::Heading(_)
+            | Block::Markup(_)
+            | Block::Paragraph(_)
+            | Block::Table(_)
+            | Block::ThematicBreak => {
+                unreachable!("Leaf blocks are never opened")
+            }
+        }
+    }
+}
+
+impl Block {
+    // calls `f` with the block, then each of its descendants, in document order
+    pub fn visit_blocks_mut(&mut self, f: &mut impl FnMut(&mut Block)) {
+        f(self);
+        match self {
+            Block::BlockQuote(BlockQuote { children, .. })
+            | Block::FootnoteDefinition(FootnoteDefinition { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. })
+            | Block::JsxComponent(JsxComponent { children, .. }) => {
+                for child in children {
+                    child.visit_blocks_mut(f);
+                }
+            }
+            Block::List(list) => {
+                for child in list.items.iter_mut().flat_map(|item| &mut item.children) {
+                    child.visit_blocks_mut(f);
+                }
+            }
+            Block::CodeBlock(_)
+            | Block::Heading(_)
+            | Block::Markup(_)
+            | Block::Paragraph(_)
+            | Block::Table(_)
+            | Block::ThematicBreak => {}
+        }
+    }
+
+    // calls `f` with each run of inline content in the block and its children
+    pub fn visit_inlines_mut(&mut self, f: &mut impl FnMut(&mut Vec<Inline>)) {
+        match self {
+            Block::Heading(Heading { content, .. }) | Block::Paragraph(content) => f(content),
+            Block::BlockQuote(BlockQuote { children, .. })
+            | Block::FootnoteDefinition(FootnoteDefinition { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. })
+            | Block::JsxComponent(JsxComponent { children, .. }) => {
+                for child in children {
+                    child.visit_inlines_mut(f);
+                }
+            }
+            Block::List(list) => {
+                for child in list.items.iter_mut().flat_map(|item| &mut item.children) {
+                    child.visit_inlines_mut(f);
+                }
+            }
+            Block::Table(table) => {
+                for cell in table
+                    .head
+                    .iter_mut()
+                    .chain(table.body.iter_mut())
+                    .flat_map(|row| &mut row.cells)
+                {
+                    f(cell);
+                }
+            }
+            Block::CodeBlock(_) | Block::Markup(_) | Block::ThematicBreak => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockQuote {
+    pub callout: Option<CalloutType>,
+    pub children: Vec<Block>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub first_line_number: Option<String>,
+    pub highlight_line_numbers: Option<String>,
+    pub title: Option<String>,
+    pub caption: Option<String>,
+    pub collapse: bool,
+    pub lines: Vec<String>,
+}
+
+/// `[^label]: text` definition, with any indented blocks following it
+#[derive(Clone, Debug, PartialEq)]
+pub struct FootnoteDefinition {
+    /// normalised label, matched against footnote references
+    pub label: String,
+    pub children: Vec<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Footnote {
+    pub children: Vec<Block>,
+
+    /// number of references to the footnote, each of which gets a back-reference link
+    pub references: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+
+    /// from a `{#custom-id}` attribute, or the slugified title made unique within the document
+    pub id: String,
+
+    /// set when the id comes from a `{#custom-id}` attribute, so is kept as written
+    pub custom_id: bool,
+
+    /// from `{.class}` attributes
+    pub classes: Vec<String>,
+    pub content: Vec<Inline>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlBlock {
+    pub element: HTMLBlockElementType,
+    pub children: Vec<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsxComponent {
+    pub component: JSXComponentType,
+    pub children: Vec<Block>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct List {
+    pub list_type: ListType,
+
+    /// first item number of an ordered list
+    pub start: Option<String>,
+
+    /// items, or blocks within an item, are separated by blank lines, so paragraphs are wrapped in `<p>`
+    pub loose: bool,
+    pub items: Vec<ListItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListItem {
+    /// task list item state, from a `[ ]` or `[x]` marker
+    pub checked: Option<bool>,
+    pub children: Vec<Block>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    /// one entry per column, `None` for columns with no alignment marker
+    pub alignments: Vec<Option<TableAlign>>,
+    pub head: Vec<TableRow>,
+    pub body: Vec<TableRow>,
+}
+
+impl Table {
+    /* merges rows parsed from a later line of the same table, padding or truncating body rows to
+     * the number of columns in the delimiter row
+     */
+    pub fn append(&mut self, mut other: Table) {
+        self.alignments.append(&mut other.alignments);
+        self.head.append(&mut other.head);
+        let columns = self.alignments.len();
+        if columns > 0 {
+            for row in &mut other.body {
+                row.cells.resize(columns, Vec::new());
+            }
+        }
+        self.body.append(&mut other.body);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableRow {
+    pub cells: Vec<Vec<Inline>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inline {
+    Code(String),
+    Emphasis(Vec<Inline>),
+    FootnoteReference(FootnoteReference),
+    Image(Image),
+
+    /// hard line break, from a line ending in two spaces or a backslash
+    LineBreak,
+    Link(Link),
+    LinkReference(LinkReference),
+
+    /// line ending within a paragraph
+    SoftBreak,
+    Strikethrough(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub href: String,
+
+    /// attributes as written in the source, including `href`
+    pub attributes: String,
+    pub has_rel: bool,
+    pub has_target: bool,
+    pub content: Vec<Inline>,
+}
+
+/// `[^label]` reference, resolved once every footnote definition is known
+#[derive(Clone, Debug, PartialEq)]
+pub struct FootnoteReference {
+    /// footnote number, from one, in order of first reference
+    pub number: usize,
+
+    /// counts references to the same footnote, from one, for unique back-reference ids
+    pub occurrence: usize,
+
+    /// footnote content, when it is output beside the reference rather than in a footnotes section
+    pub sidenote: Option<Vec<Block>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub alt: String,
+    pub src: String,
+    pub title: Option<String>,
+
+    /// set for local image files, once every image in the document is known
+    pub local: Option<LocalImage>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LocalImage {
+    /// index of the `astro:assets` import in `Document::image_assets`
+    AstroAsset(usize),
+
+    /// index of the picture in the post's `post-images` content collection entry, after the pictures
+    /// of any hand-written `<Image index={n} />` components
+    PostImage(usize),
+}
+
+/// Reference-style link, replaced by a link once link reference definitions are collected
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkReference {
+    /// normalised label, matched against link reference definitions
+    pub label: String,
+
+    /// `[text]`, without a label, is left as text when there is no matching definition
+    pub shortcut: bool,
+
+    /// reference as written in the source
+    pub source: String,
+    pub content: Vec<Inline>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkDefinition {
+    pub href: String,
+    pub title: Option<String>,
+}
+
+/// Assembles blocks, parsed line by line, into a document tree
+pub struct DocumentBuilder {
+    blocks: Vec<Block>,
+    open_blocks: Stack<Block>,
+}
+
+impl DocumentBuilder {
+    pub fn new() -> DocumentBuilder {
+        DocumentBuilder {
+            blocks: Vec::new(),
+            open_blocks: Stack::new(),
+        }
+    }
+
+    pub fn peek(&self) -> Option<&Block> {
+        self.open_blocks.peek()
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut Block> {
+        self.open_blocks.peek_mut()
+    }
+
+    // adds a block to the innermost open block, or to the document, when no blocks are open
+    pub fn push(&mut self, block: Block) {
+        match self.open_blocks.peek_mut() {
+            Some(parent) => parent.push_child(block),
+            None => self.blocks.push(block),
+        }
+    }
+
+    // following blocks are added to this one, until it is closed
+    pub fn open(&mut self, block: Block) {
+        self.open_blocks.push(block);
+    }
+
+    pub fn close(&mut self) {
+        if let Some(block) = self.open_blocks.pop() {
+            self.push(block);
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<Block> {
+        while !self.open_blocks.is_empty() {
+            self.close();
+        }
+        self.blocks
+    }
+}
@@ -0,0 +1,59 @@
+use crate::parser::ast::{Block, DocumentBuilder, Inline, List, ListItem, ListType};
+
+#[test]
+pub fn test_document_builder() {
+    let paragraph = |text: &str| Block::Paragraph(vec![Inline::Text(String::from(text))]);
+    let mut document = DocumentBuilder::new();
+    document.push(paragraph("Before the list"));
+    document.open(Block::List(List {
+        list_type: ListType::Unordered,
+        start: None,
+        loose: false,
+        items: vec![ListItem {
+            checked: None,
+            children: vec![paragraph("First item")],
+        }],
+    }));
+    document.push(paragraph("More on the first item"));
+    assert!(matches!(document.peek(), Some(Block::List(_))));
+    document.close();
+    document.push(paragraph("After the list"));
+    assert_eq!(
+        document.finish(),
+        vec![
+            paragraph("Before the list"),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![ListItem {
+                    checked: None,
+                    children: vec![paragraph("First item"), paragraph("More on the first item")]
+                }]
+            }),
+            paragraph("After the list")
+        ]
+    );
+
+    // blocks still open are closed when the document is finished
+    let mut document = DocumentBuilder::new();
+    document.open(Block::List(List {
+        list_type: ListType::Ordered,
+        start: Some(String::from("1")),
+        loose: false,
+        items: Vec::new(),
+    }));
+    document.push(paragraph("Only item"));
+    assert_eq!(
+        document.finish(),
+        vec![Block::List(List {
+            list_type: ListType::Ordered,
+            start: Some(String::from("1")),
+            loose: false,
+            items: vec![ListItem {
+                checked: None,
+                children: vec![paragraph("Only item")]
+            }]
+        })]
+    );
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests;
+
+use crate::parser::{ast::Inline, merge_inline_text};
+use std::collections::HashMap;
+
+/// Run of `*` or `_` characters, which may open or close emphasis, following the CommonMark
+/// delimiter run rules
+#[derive(Debug, PartialEq)]
+pub struct DelimiterRun {
+    pub character: char,
+
+    /// characters not yet used to open or close emphasis
+    pub length: usize,
+    pub original_length: usize,
+    pub can_open: bool,
+    pub can_close: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum InlineToken {
+    Delimiter(DelimiterRun),
+    Inline(Inline),
+}
+
+impl InlineToken {
+    // unused delimiter characters are kept as text
+    fn into_inline(self) -> Option<Inline> {
+        match self {
+            InlineToken::Delimiter(DelimiterRun { length: 0, .. }) => None,
+            InlineToken::Delimiter(DelimiterRun {
+                character, length, ..
+            }) => Some(Inline::Text(character.to_string().repeat(length))),
+            InlineToken::Inline(inline) => Some(inline),
+        }
+    }
+}
+
+// the start and end of the line count as whitespace
+fn is_whitespace(character: Option<char>) -> bool {
+    character.map_or(true, char::is_whitespace)
+}
+
+// Unicode punctuation and symbols
+fn is_punctuation(character: Option<char>) -> bool {
+    character.is_some_and(|value| !value.is_alphanumeric() && !value.is_whitespace())
+}
+
+/* classifies a run of `length` delimiter characters from the characters either side of it; an
+ * underscore run, unlike an asterisk run, cannot open or close emphasis within a word
+ */
+pub fn delimiter_run(
+    character: char,
+    length: usize,
+    previous: Option<char>,
+    next: Option<char>,
+) -> DelimiterRun {
+    let left_flanking = !is_whitespace(next)
+        && (!is_punctuation(next) || is_whitespace(previous) || is_punctuation(previous));
+    let right_flanking = !is_whitespace(previous)
+        && (!is_punctuation(previous) || is_whitespace(next) || is_punctuation(next));
+    let (can_open, can_close) = if character == '_' {
+        (
+            left_flanking && (!right_flanking || is_punctuation(previous)),
+            right_flanking && (!left_flanking || is_punctuation(next)),
+        )
+    } else {
+        (left_flanking, right_flanking)
+    };
+    DelimiterRun {
+        character,
+        length,
+        original_length: length,
+        can_open,
+        can_close,
+    }
+}
+
+// an opener and closer pair cannot have a combined length which is a multiple of three
+fn is_matching_opener(opener: &DelimiterRun, closer: &DelimiterRun) -> bool {
+    let multiple_of_three = (opener.can_close || closer.can_open)
+        && (opener.original_length + closer.original_length) % 3 == 0
+        && !(opener.original_length % 3 == 0 && closer.original_length % 3 == 0);
+    opener.character == closer.character
+        && opener.can_open
+        && opener.length > 0
+        && !multiple_of_three
+}
+
+/* matches closing delimiter runs with the nearest opening run, wrapping the tokens between in
+ * emphasis, or strong emphasis where both runs have at least two characters left
+ */
+pub fn process_emphasis(mut tokens: Vec<InlineToken>) -> Vec<Inline> {
+    // lowest index to search from for an opener, by closer character, can open and length mod three
+    let mut openers_bottom: HashMap<(char, bool, usize), usize> = HashMap::new();
+    let mut closer_index = 0;
+    while closer_index < tokens.len() {
+        let InlineToken::Delimiter(closer) = &tokens[closer_index] else {
+            closer_index += 1;
+            continue;
+        };
+        if !closer.can_close || closer.length == 0 {
+            closer_index += 1;
+            continue;
+        }
+        let key = (
+            closer.character,
+            closer.can_open,
+            closer.original_length % 3,
+        );
+        let bottom = openers_bottom.get(&key).copied().unwrap_or(0);
+        let opener = (bottom..closer_index)
+            .rev()
+            .find_map(|index| match &tokens[index] {
+                InlineToken::Delimiter(opener) if is_matching_opener(opener, closer) => {
+                    Some((index, opener.length))
+                }
+                _ => None,
+            });
+        let Some((opener_index, opener_length)) = opener else {
+            openers_bottom.insert(key, closer_index);
+            closer_index += 1;
+            continue;
+        };
+
+        let used = if opener_length >= 2 && closer.length >= 2 {
+            2
+        } else {
+            1
+        };
+        for index in [opener_index, closer_index] {
+            if let InlineToken::Delimiter(run) = &mut tokens[index] {
+                run.length -= used;
+            }
+        }
+        let content = merge_inline_text(
+            tokens
+                .drain(opener_index + 1..closer_index)
+                .filter_map(InlineToken::into_inline)
+                .collect(),
+        );
+        let inline = if used == 2 {
+            Inline::Strong(content)
+        } else {
+            Inline::Emphasis(content)
+        };
+        tokens.insert(opener_index + 1, InlineToken::Inline(inline));
+        closer_index = opener_index + 2;
+
+        // tokens between the pair are gone, so no search may start inside them
+        for value in openers_bottom.values_mut() {
+            if *value > opener_index {
+                *value = opener_index + 1;
+            }
+        }
+    }
+    merge_inline_text(
+        tokens
+            .into_iter()
+            .filter_map(InlineToken::into_inline)
+            .collect(),
+    )
+}
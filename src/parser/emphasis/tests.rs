@@ -0,0 +1,119 @@
+use crate::parser::{
+    ast::Inline,
+    emphasis::{delimiter_run, process_emphasis, DelimiterRun, InlineToken},
+    parse_inline_wrap_text,
+};
+
+fn text(value: &str) -> Inline {
+    Inline::Text(String::from(value))
+}
+
+fn parse(line: &str) -> Vec<Inline> {
+    let (_, inlines) = parse_inline_wrap_text(line).unwrap();
+    inlines
+}
+
+#[test]
+pub fn test_delimiter_run() {
+    assert_eq!(
+        delimiter_run('*', 2, None, Some('f')),
+        DelimiterRun {
+            character: '*',
+            length: 2,
+            original_length: 2,
+            can_open: true,
+            can_close: false
+        }
+    );
+
+    // intraword asterisks both open and close, but underscores do neither
+    let run = delimiter_run('*', 1, Some('2'), Some('3'));
+    assert!(run.can_open && run.can_close);
+    let run = delimiter_run('_', 1, Some('e'), Some('c'));
+    assert!(!run.can_open && !run.can_close);
+
+    // surrounded by whitespace
+    let run = delimiter_run('*', 1, Some(' '), Some(' '));
+    assert!(!run.can_open && !run.can_close);
+
+    // punctuation before the run
+    let run = delimiter_run('_', 1, Some('('), Some('a'));
+    assert!(run.can_open && !run.can_close);
+}
+
+#[test]
+pub fn test_process_emphasis() {
+    let tokens = vec![
+        InlineToken::Delimiter(delimiter_run('*', 2, None, Some('f'))),
+        InlineToken::Inline(text("foo")),
+        InlineToken::Delimiter(delimiter_run('*', 1, Some('o'), None)),
+    ];
+    assert_eq!(
+        process_emphasis(tokens),
+        vec![text("*"), Inline::Emphasis(vec![text("foo")])]
+    );
+}
+
+#[test]
+pub fn test_parse_emphasis() {
+    assert_eq!(
+        parse("_italic_ and __bold__"),
+        vec![
+            Inline::Emphasis(vec![text("italic")]),
+            text(" and "),
+            Inline::Strong(vec![text("bold")])
+        ]
+    );
+    assert_eq!(
+        parse("***both***"),
+        vec![Inline::Emphasis(vec![Inline::Strong(vec![text("both")])])]
+    );
+    assert_eq!(
+        parse("**bold *and italic* text**"),
+        vec![Inline::Strong(vec![
+            text("bold "),
+            Inline::Emphasis(vec![text("and italic")]),
+            text(" text")
+        ])]
+    );
+    assert_eq!(
+        parse("*foo**bar**baz*"),
+        vec![Inline::Emphasis(vec![
+            text("foo"),
+            Inline::Strong(vec![text("bar")]),
+            text("baz")
+        ])]
+    );
+    assert_eq!(
+        parse("2*3*4"),
+        vec![text("2"), Inline::Emphasis(vec![text("3")]), text("4")]
+    );
+    assert_eq!(
+        parse("_foo_bar_ and snake_case_name"),
+        vec![
+            Inline::Emphasis(vec![text("foo_bar")]),
+            text(" and snake_case_name")
+        ]
+    );
+    assert_eq!(parse("* not emphasis *"), vec![text("* not emphasis *")]);
+    assert_eq!(
+        parse("*foo**"),
+        vec![Inline::Emphasis(vec![text("foo")]), text("*")]
+    );
+}
+
+#[test]
+pub fn test_parse_emphasis_with_markup() {
+    // code spans and links take precedence over emphasis
+    assert_eq!(
+        parse("*foo`*`"),
+        vec![text("*foo"), Inline::Code(String::from("*"))]
+    );
+    assert_eq!(
+        parse("*see `code`*"),
+        vec![Inline::Emphasis(vec![
+            text("see "),
+            Inline::Code(String::from("code"))
+        ])]
+    );
+}
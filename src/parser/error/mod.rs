@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests;
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use nom::{
+    error::{ErrorKind, ParseError as NomParseError},
+    Err, Offset,
+};
+use std::io;
+use thiserror::Error;
+
+pub type IResult<I, O> = nom::IResult<I, O, LineError<I>>;
+
+/// Problems found in MDX source
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ParseErrorKind {
+    #[error("anchor element is missing an `href` attribute")]
+    MissingHref,
+
+    #[error("image is missing alt text")]
+    MissingAltText,
+
+    #[error("unable to parse `{0}` component props")]
+    InvalidComponentProps(String),
+
+    #[error("expected a whole number of seconds for HowTo step `{0}`, got `{1}`")]
+    InvalidHowToStepTime(String, String),
+
+    #[error("invalid frontmatter: {0}")]
+    InvalidFrontmatter(String),
+
+    #[error("unrecognised HTML block element `{0}`")]
+    UnrecognisedBlockElement(String),
+
+    #[error("no link reference definition for `{0}`")]
+    UnresolvedLinkReference(String),
+
+    #[error("unexpected input ({0:?})")]
+    Nom(ErrorKind),
+}
+
+impl ParseErrorKind {
+    fn help(&self) -> Option<String> {
+        match self {
+            ParseErrorKind::MissingHref => Some(String::from(
+                "add a link target, for example `<a href=\"https://example.com\">`",
+            )),
+            ParseErrorKind::MissingAltText => Some(String::from(
+                "describe the image for screen readers, for example `![Agfa Isolette camera](./camera.jpg)`, or write a purely decorative image as `<img src=\"...\" alt=\"\" />`",
+            )),
+            ParseErrorKind::InvalidComponentProps(_) => Some(String::from(
+                "props should have the form `name=\"value\"` or ``name={`value`}``",
+            )),
+            ParseErrorKind::InvalidHowToStepTime(_, _) => Some(String::from(
+                "give the video time in seconds, for example `start={90}`",
+            )),
+            ParseErrorKind::InvalidFrontmatter(_) => Some(String::from(
+                "frontmatter should be YAML `key: value` fields between `---` lines",
+            )),
+            ParseErrorKind::UnrecognisedBlockElement(_) => Some(String::from(
+                "supported HTML block elements are `dl`, `div` and `figure`",
+            )),
+            ParseErrorKind::UnresolvedLinkReference(label) => Some(format!(
+                "define the link anywhere in the document, for example `[{label}]: https://example.com`"
+            )),
+            ParseErrorKind::Nom(_) => None,
+        }
+    }
+}
+
+/// nom error, carrying the kind of problem along with the input where it was found
+#[derive(Debug, PartialEq)]
+pub struct LineError<I> {
+    pub input: I,
+    pub kind: ParseErrorKind,
+}
+
+impl<I> LineError<I> {
+    pub fn new(input: I, code: ErrorKind) -> Self {
+        LineError {
+            input,
+            kind: ParseErrorKind::Nom(code),
+        }
+    }
+
+    // unrecoverable error, which stops alternative parsers being tried
+    pub fn failure(input: I, kind: ParseErrorKind) -> Err<Self> {
+        Err::Failure(LineError { input, kind })
+    }
+}
+
+impl<I> NomParseError<I> for LineError<I> {
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        LineError::new(input, kind)
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+/// Error returned when MDX source cannot be converted
+#[derive(Debug, Diagnostic, Error)]
+pub enum ParseError {
+    #[error("unable to access `{path}`")]
+    #[diagnostic(code(cmessless::io))]
+    Io {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("unable to find a post slug in the path `{path}`")]
+    #[diagnostic(
+        code(cmessless::slug),
+        help("name the file after the post, or put `index.mdx` in a directory named after it")
+    )]
+    Slug { path: String },
+
+    #[error("unable to format the output for `{path}`: {message}")]
+    #[diagnostic(code(cmessless::format))]
+    Format { path: String, message: String },
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Source(Box<SourceError>),
+}
+
+/// Problem in MDX source, with its location
+#[derive(Debug, Diagnostic, Error)]
+#[error("{kind} ({file}:{line}:{column})")]
+#[diagnostic(code(cmessless::parse))]
+pub struct SourceError {
+    pub kind: ParseErrorKind,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    #[source_code]
+    src: NamedSource<String>,
+    #[label("{kind}")]
+    span: SourceSpan,
+    #[help]
+    help: Option<String>,
+}
+
+impl SourceError {
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+}
+
+impl ParseError {
+    /* `line_number` is one-based and `error.input` should be a slice of `line`, which is itself a
+     * slice of `source`
+     */
+    pub fn from_line_error(
+        error: &LineError<&str>,
+        file: &str,
+        source: &str,
+        line: &str,
+        line_number: usize,
+    ) -> ParseError {
+        let offset = source.offset(line) + line.offset(error.input);
+        let column = line[..line.offset(error.input)].chars().count() + 1;
+        ParseError::Source(Box::new(SourceError {
+            kind: error.kind.clone(),
+            file: file.to_string(),
+            line: line_number,
+            column,
+            src: NamedSource::new(file, source.to_string()),
+            span: (offset, error.input.len()).into(),
+            help: error.kind.help(),
+        }))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::parser::error::{LineError, ParseError, ParseErrorKind};
+use nom::{error::ErrorKind, Err};
+
+#[test]
+pub fn test_line_error_failure() {
+    let mdx_line = "<a to=\"https://www.example.com\">";
+    assert_eq!(
+        LineError::failure(mdx_line, ParseErrorKind::MissingHref),
+        Err::Failure(LineError {
+            input: mdx_line,
+            kind: ParseErrorKind::MissingHref
+        })
+    );
+
+    assert_eq!(
+        LineError::new(mdx_line, ErrorKind::Tag),
+        LineError {
+            input: mdx_line,
+            kind: ParseErrorKind::Nom(ErrorKind::Tag)
+        }
+    );
+}
+
+#[test]
+pub fn test_parse_error_from_line_error() {
+    let source = "## Feedback\n\nGo to <a to=\"/contact\">our site</a>.\n";
+    let line = source.lines().nth(2).unwrap();
+    let error = LineError {
+        input: &line[6..22],
+        kind: ParseErrorKind::MissingHref,
+    };
+    match ParseError::from_line_error(&error, "index.mdx", source, line, 3) {
+        ParseError::Source(error) => {
+            assert_eq!(error.kind, ParseErrorKind::MissingHref);
+            assert_eq!(error.file, "index.mdx");
+            assert_eq!(error.line, 3);
+            assert_eq!(error.column, 7);
+            assert_eq!(error.span().offset(), 19);
+            assert_eq!(error.span().len(), 16);
+        }
+        _ => panic!("Expected a source error"),
+    }
+}
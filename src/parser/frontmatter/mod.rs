@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests;
+
+use crate::parser::{
+    ast::FrontmatterValue,
+    error::{IResult, LineError, ParseError, ParseErrorKind},
+    LineType,
+};
+use nom::{
+    bytes::complete::tag, character::complete::space0, combinator::eof, sequence::terminated,
+    Parser,
+};
+use yaml_rust2::{Yaml, YamlLoader};
+
+fn parse_frontmatter_delimiter(line: &str) -> IResult<&str, &str> {
+    let (line, _) = terminated(tag("---"), (space0, eof)).parse(line)?;
+    Ok((line, ""))
+}
+
+fn parse_frontmatter_line(line: &str) -> (Option<String>, LineType) {
+    match parse_frontmatter_delimiter(line) {
+        Ok((_frontmatter_line, _)) => (None, LineType::FrontmatterDelimiter),
+        Err(_) => (Some(String::from(line)), LineType::Frontmatter),
+    }
+}
+
+pub fn parse_frontmatter(source: &str) -> usize {
+    let mut frontmatter_open = false;
+
+    for (line_index, line_content) in source.lines().enumerate() {
+        let (_frontmatter_line_option, line_type) = parse_frontmatter_line(line_content);
+        if line_type == LineType::FrontmatterDelimiter {
+            frontmatter_open = !frontmatter_open;
+            if !frontmatter_open {
+                return line_index + 1;
+            }
+        } else if !frontmatter_open {
+            /* first line of file (with content) is not frontmatter delimiter so assume there is no
+             * frontmatter
+             */
+            return 0;
+        };
+    }
+
+    // without a closing delimiter, the opening `---` is a thematic break
+    0
+}
+
+/* parses the YAML between the frontmatter delimiters into its top-level fields, in source order;
+ * `frontmatter_end_line_number` is the value returned by `parse_frontmatter`
+ */
+pub fn parse_frontmatter_fields(
+    source: &str,
+    file: &str,
+    frontmatter_end_line_number: usize,
+) -> Result<Vec<(String, FrontmatterValue)>, ParseError> {
+    let lines: Vec<&str> = source
+        .lines()
+        .take(frontmatter_end_line_number.saturating_sub(1))
+        .collect();
+    if lines.len() < 2 {
+        return Ok(Vec::new());
+    }
+    let yaml_source = lines[1..].join("\n");
+
+    let error = |line_index: usize, column: usize, message: &str| {
+        // errors at the end of input are reported on the last frontmatter line
+        let line_index = line_index.clamp(1, lines.len() - 1);
+        let line = lines[line_index];
+        let offset = line
+            .char_indices()
+            .nth(column)
+            .map_or(line.len(), |(offset, _)| offset);
+        let line_error = LineError {
+            input: &line[offset..],
+            kind: ParseErrorKind::InvalidFrontmatter(message.to_string()),
+        };
+        ParseError::from_line_error(&line_error, file, source, line, line_index + 1)
+    };
+
+    let documents = YamlLoader::load_from_str(&yaml_source).map_err(|scan_error| {
+        let marker = scan_error.marker();
+        error(marker.line(), marker.col(), scan_error.info())
+    })?;
+    match documents.into_iter().next() {
+        Some(Yaml::Hash(fields)) => Ok(fields
+            .into_iter()
+            .map(|(key, value)| (frontmatter_key(key), frontmatter_value(value)))
+            .collect()),
+        None | Some(Yaml::Null) => Ok(Vec::new()),
+        Some(_) => Err(error(1, 0, "expected `key: value` fields")),
+    }
+}
+
+fn frontmatter_key(key: Yaml) -> String {
+    match frontmatter_value(key) {
+        FrontmatterValue::Boolean(value) => value.to_string(),
+        FrontmatterValue::Integer(value) => value.to_string(),
+        FrontmatterValue::Real(value) | FrontmatterValue::String(value) => value,
+        _ => String::new(),
+    }
+}
+
+fn frontmatter_value(value: Yaml) -> FrontmatterValue {
+    match value {
+        Yaml::Real(value) => FrontmatterValue::Real(value),
+        Yaml::Integer(value) => FrontmatterValue::Integer(value),
+        Yaml::String(value) => FrontmatterValue::String(value),
+        Yaml::Boolean(value) => FrontmatterValue::Boolean(value),
+        Yaml::Array(values) => {
+            FrontmatterValue::List(values.into_iter().map(frontmatter_value).collect())
+        }
+        Yaml::Hash(fields) => FrontmatterValue::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (frontmatter_key(key), frontmatter_value(value)))
+                .collect(),
+        ),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => FrontmatterValue::Null,
+    }
+}
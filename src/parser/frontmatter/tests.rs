@@ -0,0 +1,121 @@
+use crate::parser::{
+    ast::FrontmatterValue,
+    error::{ParseError, ParseErrorKind},
+    frontmatter::{parse_frontmatter, parse_frontmatter_fields},
+};
+
+#[test]
+pub fn test_parse_frontmatter() {
+    let mdx = "---\ntitle: Getting Started\n---\n\n## What is NewTech?\n";
+    assert_eq!(parse_frontmatter(mdx), 3);
+
+    let mdx = "## What is NewTech?\n";
+    assert_eq!(parse_frontmatter(mdx), 0);
+
+    // frontmatter must open the file, and be closed
+    let mdx = "\n---\ntitle: Getting Started\n---\n";
+    assert_eq!(parse_frontmatter(mdx), 0);
+
+    let mdx = "---\n\n## What is NewTech?\n";
+    assert_eq!(parse_frontmatter(mdx), 0);
+
+    let mdx = "----\ntitle: Getting Started\n----\n";
+    assert_eq!(parse_frontmatter(mdx), 0);
+}
+
+#[test]
+pub fn test_parse_frontmatter_fields() {
+    let mdx = "---
+title: Getting Started with NewTech
+datePublished: '2023-08-11'
+draft: false
+readingTime: 5
+rating: 4.5
+tags:
+  - rust
+  - astro
+seo:
+  noIndex: true
+---
+
+## What is NewTech?
+";
+    assert_eq!(
+        parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)).unwrap(),
+        vec![
+            (
+                String::from("title"),
+                FrontmatterValue::String(String::from("Getting Started with NewTech"))
+            ),
+            (
+                String::from("datePublished"),
+                FrontmatterValue::String(String::from("2023-08-11"))
+            ),
+            (String::from("draft"), FrontmatterValue::Boolean(false)),
+            (String::from("readingTime"), FrontmatterValue::Integer(5)),
+            (
+                String::from("rating"),
+                FrontmatterValue::Real(String::from("4.5"))
+            ),
+            (
+                String::from("tags"),
+                FrontmatterValue::List(vec![
+                    FrontmatterValue::String(String::from("rust")),
+                    FrontmatterValue::String(String::from("astro"))
+                ])
+            ),
+            (
+                String::from("seo"),
+                FrontmatterValue::Map(vec![(
+                    String::from("noIndex"),
+                    FrontmatterValue::Boolean(true)
+                )])
+            ),
+        ]
+    );
+
+    // no frontmatter, or empty frontmatter
+    let mdx = "## What is NewTech?\n";
+    assert_eq!(
+        parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)).unwrap(),
+        Vec::new()
+    );
+    let mdx = "---\n---\n## What is NewTech?\n";
+    assert_eq!(
+        parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)).unwrap(),
+        Vec::new()
+    );
+
+    // errors point at the source line
+    let mdx = "---\ntitle: Getting Started\ntags: [rust, astro\n---\n";
+    match parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)) {
+        Err(ParseError::Source(error)) => {
+            assert!(matches!(error.kind, ParseErrorKind::InvalidFrontmatter(_)));
+            assert_eq!(error.file, "index.mdx");
+            assert!(error.line > 1);
+        }
+        _ => panic!("Expected invalid frontmatter error"),
+    }
+
+    // the reported line matches the labelled one, for errors at the end of input
+    let mdx = "---\ntitle: [unclosed\n---\n";
+    match parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)) {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(error.line, 2);
+            assert_eq!(mdx[..error.span().offset()].matches('\n').count(), 1);
+        }
+        _ => panic!("Expected invalid frontmatter error"),
+    }
+
+    let mdx = "---\n- rust\n- astro\n---\n";
+    match parse_frontmatter_fields(mdx, "index.mdx", parse_frontmatter(mdx)) {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::InvalidFrontmatter(String::from("expected `key: value` fields"))
+            );
+            assert_eq!(error.line, 2);
+        }
+        _ => panic!("Expected invalid frontmatter error"),
+    }
+}
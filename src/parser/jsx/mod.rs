@@ -3,7 +3,9 @@ mod tests;
 
 use crate::{
     parser::{
-        escape_code, form_fenced_code_block_first_line, form_fenced_code_block_last_line,
+        ast::Block,
+        error::{IResult, LineError, ParseErrorKind},
+        form_fenced_code_block_first_line, form_fenced_code_block_last_line,
         parse_closing_html_tag, parse_html_tag_attributes, parse_opening_html_tag,
         parse_opening_html_tag_end, parse_opening_html_tag_start, parse_self_closing_html_tag,
         parse_self_closing_html_tag_end, HTMLTagType, LineType,
@@ -14,18 +16,28 @@ use crate::{
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until},
+    character::complete::digit1,
     combinator::{all_consuming, map, rest, value},
-    error::{Error, ErrorKind},
+    error::ErrorKind,
     sequence::{delimited, preceded, terminated},
-    Err, IResult, Parser,
+    Err, Parser,
 };
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+// opening fence of a fenced code block, which only a run of the same character at least as long closes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CodeFence {
+    pub character: char,
+    pub length: usize,
+    pub indentation: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum JSXComponentType {
+    Callout,
     CodeFragment,
     CodeFragmentOpening,
-    FencedCodeBlock,
+    FencedCodeBlock(CodeFence),
     GatsbyNotMaintained,
     HowTo,
     HowToOpening,
@@ -39,6 +51,10 @@ pub enum JSXComponentType {
     Poll,
     PollOpening,
     Questions,
+    Sidenote,
+
+    /// `<TableOfContents />` marker, replaced by the page outline
+    TableOfContents,
     Tweet,
     Video,
     VideoOpening,
@@ -267,6 +283,14 @@ impl JSXComponentRegister {
         self.components.push(component);
     }
 
+    // opening fence, when the innermost open component is a fenced code block
+    pub fn code_fence(&self) -> Option<CodeFence> {
+        match self.components.peek() {
+            Some(JSXComponentType::FencedCodeBlock(fence)) => Some(*fence),
+            _ => None,
+        }
+    }
+
     pub fn add_how_to_section(&mut self, name: &str) -> usize {
         self.how_to
             .as_mut()
@@ -306,26 +330,28 @@ impl JSXComponentRegister {
             .add_step_video(video);
     }
 
-    pub fn add_how_to_step_start(&mut self, start: &str) {
-        let start_int: u64 = start
-            .parse()
-            .expect("Error parsing HowTo step video start time");
+    pub fn add_how_to_step_start(&mut self, start: &str) -> Result<(), ParseErrorKind> {
+        let start_int: u64 = start.parse().map_err(|_| {
+            ParseErrorKind::InvalidHowToStepTime(String::from("start"), start.to_string())
+        })?;
         let () = &self
             .how_to
             .as_mut()
             .expect("Error adding HowTo step video start time")
             .add_step_start(start_int);
+        Ok(())
     }
 
-    pub fn add_how_to_step_end(&mut self, end: &str) {
-        let end_int: u64 = end
-            .parse()
-            .expect("Error parsing HowTo step video end time");
+    pub fn add_how_to_step_end(&mut self, end: &str) -> Result<(), ParseErrorKind> {
+        let end_int: u64 = end.parse().map_err(|_| {
+            ParseErrorKind::InvalidHowToStepTime(String::from("end"), end.to_string())
+        })?;
         let () = &self
             .how_to
             .as_mut()
             .expect("Error adding HowTo step video end time")
             .add_step_end(end_int);
+        Ok(())
     }
 
     pub fn add_how_to_direction(&mut self, text: &str) -> usize {
@@ -419,7 +445,7 @@ fn form_jsx_component_first_line<'a>(
         HTMLTagType::Opening | HTMLTagType::OpeningStart | HTMLTagType::SelfClosing => {
             Ok((remaining_line, (line.to_string(), attributes, tag_type, 0)))
         }
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -433,7 +459,7 @@ fn form_jsx_component_opening_line(
         HTMLTagType::Opening | HTMLTagType::OpeningStart | HTMLTagType::SelfClosing => {
             Ok((remaining_line, (line.to_string(), attributes, tag_type, 0)))
         }
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -446,7 +472,7 @@ fn form_jsx_component_last_line<'a>(
     match tag_type {
         HTMLTagType::Closing => Ok((remaining_line, (line.to_string(), tag_type, 0))),
         HTMLTagType::Opening | HTMLTagType::OpeningStart | HTMLTagType::SelfClosing => {
-            Err(Err::Error(Error::new(line, ErrorKind::Tag)))
+            Err(Err::Error(LineError::new(line, ErrorKind::Tag)))
         }
     }
 }
@@ -494,7 +520,7 @@ fn form_how_to_component_first_line(line: &str) -> IResult<&str, (String, &str,
             remaining_line,
             (markup, attributes, LineType::HowTo, indentation),
         )),
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -521,7 +547,7 @@ fn form_how_to_section_component_first_line(
             remaining_line,
             (markup, attributes, LineType::HowToSection, indentation),
         )),
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -543,7 +569,7 @@ fn form_how_to_step_component_first_line(
             remaining_line,
             (markup, attributes, LineType::HowToStep, indentation),
         )),
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -575,7 +601,7 @@ fn form_how_to_direction_component_first_line(
             remaining_line,
             (markup, attributes, LineType::HowToDirection, indentation),
         )),
-        HTMLTagType::Closing => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        HTMLTagType::Closing => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
@@ -585,6 +611,19 @@ pub fn form_image_component(line: &str) -> IResult<&str, (String, LineType, usiz
     Ok(("", (format!("<Image{attributes}/>"), LineType::Image, 0)))
 }
 
+// `index={n}` attribute of a hand-written `Image` component, picking its post-images picture
+pub fn parse_image_component_index(markup: &str) -> IResult<&str, usize> {
+    let (remaining_markup, index) = preceded(
+        (take_until(" index={"), tag(" index={")),
+        terminated(digit1, tag("}")),
+    )
+    .parse(markup)?;
+    match index.parse() {
+        Ok(value) => Ok((remaining_markup, value)),
+        Err(_) => Err(Err::Error(LineError::new(markup, ErrorKind::Digit))),
+    }
+}
+
 pub fn form_gatsby_not_maintained_component(
     line: &str,
 ) -> IResult<&str, (String, LineType, usize)> {
@@ -626,6 +665,19 @@ pub fn form_questions_component(line: &str) -> IResult<&str, (String, LineType,
     ))
 }
 
+pub fn form_table_of_contents_component(line: &str) -> IResult<&str, (String, LineType, usize)> {
+    let component_identifier = "TableOfContents";
+    let (_, attributes) = parse_jsx_component(line, component_identifier)?;
+    Ok((
+        "",
+        (
+            format!("<TableOfContents{attributes}/>"),
+            LineType::TableOfContents,
+            0,
+        ),
+    ))
+}
+
 pub fn form_video_component_first_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
     let component_identifier = "Video";
     let (_, (__parsed_value_, jsx_tag_type)) =
@@ -844,21 +896,45 @@ pub fn form_video_component_last_line(line: &str) -> IResult<&str, (String, Line
     ))
 }
 
-pub fn parse_open_jsx_block(
-    line: &str,
+fn parse_component_props<'a>(
+    attributes: &'a str,
+    component_identifier: &str,
+) -> Result<Vec<(&'a str, &'a str)>, LineError<&'a str>> {
+    match parse_html_tag_attributes(attributes) {
+        Ok((_, attributes_vector)) => Ok(attributes_vector),
+        Err(_) => Err(LineError {
+            input: attributes,
+            kind: ParseErrorKind::InvalidComponentProps(component_identifier.to_string()),
+        }),
+    }
+}
+
+pub fn parse_open_jsx_block<'a>(
+    line: &'a str,
     open_jsx_component_register: &mut JSXComponentRegister,
-) -> Option<(String, LineType, usize)> {
+) -> Result<Option<(Block, LineType, usize)>, LineError<&'a str>> {
     let open_jsx_component_type = open_jsx_component_register.peek();
-    match open_jsx_component_type {
+
+    // fenced code blocks may open within HowTo components
+    if let Some(
+        JSXComponentType::HowTo
+        | JSXComponentType::HowToSection
+        | JSXComponentType::HowToStep
+        | JSXComponentType::HowToDirection,
+    ) = open_jsx_component_type
+    {
+        if let Ok((_, value)) = form_fenced_code_block_first_line(line) {
+            return Ok(Some(value));
+        }
+    }
+
+    let result = match open_jsx_component_type {
         Some(JSXComponentType::HowToOpening) => match form_how_to_component_opening_line(line) {
             Ok((_, (line, attributes, line_type, level))) => {
                 if line.is_empty() {
                     None
                 } else {
-                    let (_, attributes_vector) = parse_html_tag_attributes(attributes)
-                        .unwrap_or_else(|_| {
-                            panic!("[ ERROR ] Unable to parse HowTo component props: {line}")
-                        });
+                    let attributes_vector = parse_component_props(attributes, "HowTo")?;
                     for (key, value) in attributes_vector {
                         open_jsx_component_register.insert_prop(key, value);
                     }
@@ -887,8 +963,8 @@ pub fn parse_open_jsx_block(
             }
             Err(_) => Some((line.to_string(), LineType::JSXComponent, 0)),
         },
-        Some(JSXComponentType::FencedCodeBlock) => {
-            match alt((form_fenced_code_block_last_line,)).parse(line) {
+        Some(JSXComponentType::FencedCodeBlock(fence)) => {
+            match form_fenced_code_block_last_line(line, *fence) {
                 Ok((_, (line, line_type, level))) => {
                     if line.is_empty() {
                         None
@@ -896,15 +972,25 @@ pub fn parse_open_jsx_block(
                         Some((line, line_type, level))
                     }
                 }
-                Err(_) => Some((escape_code(line), LineType::FencedCodeBlockOpen, 0)),
+
+                // code lines lose up to as much indentation as the opening fence had
+                Err(_) => {
+                    let indentation = line
+                        .chars()
+                        .take(fence.indentation)
+                        .take_while(|c| *c == ' ')
+                        .count();
+                    Some((
+                        line[indentation..].to_string(),
+                        LineType::FencedCodeBlockOpen,
+                        0,
+                    ))
+                }
             }
         }
         Some(JSXComponentType::HowTo) => match form_how_to_section_component_first_line(line) {
             Ok((_, (line, attributes, line_type, level))) => {
-                let (_, attributes_vector) =
-                    parse_html_tag_attributes(attributes).unwrap_or_else(|_| {
-                        panic!("[ ERROR ] Unable to parse HowToSection component props: {line}")
-                    });
+                let attributes_vector = parse_component_props(attributes, "HowToSection")?;
                 for (key, value) in &attributes_vector {
                     open_jsx_component_register.insert_prop(key, value);
                 }
@@ -934,7 +1020,6 @@ pub fn parse_open_jsx_block(
                 }
             }
             Err(_) => match alt((
-                form_fenced_code_block_first_line,
                 form_video_component_first_line,
                 form_how_to_component_last_line,
             ))
@@ -953,10 +1038,7 @@ pub fn parse_open_jsx_block(
         Some(JSXComponentType::HowToSectionOpening) => {
             match form_how_to_section_component_opening_line(line) {
                 Ok((_, (line, attributes, line_type, level))) => {
-                    let (_, attributes_vector) = parse_html_tag_attributes(attributes)
-                        .unwrap_or_else(|_| {
-                            panic!("[ ERROR ] Unable to parse HowToStep component props: {line}")
-                        });
+                    let attributes_vector = parse_component_props(attributes, "HowToSection")?;
                     match attributes_vector
                         .iter()
                         .find(|&&(key, _value)| key == "name")
@@ -985,10 +1067,7 @@ pub fn parse_open_jsx_block(
         }
         Some(JSXComponentType::HowToSection) => match form_how_to_step_component_first_line(line) {
             Ok((_, (line, attributes, line_type, level))) => {
-                let (_, attributes_vector) =
-                    parse_html_tag_attributes(attributes).unwrap_or_else(|_| {
-                        panic!("[ ERROR ] Unable to parse HowToStep component props: {line}")
-                    });
+                let attributes_vector = parse_component_props(attributes, "HowToStep")?;
                 let (section_position, position) = open_jsx_component_register.add_how_to_step();
                 let mut attributes_markup_vector: Vec<String> = Vec::new();
                 attributes_markup_vector.push(format!(
@@ -1009,11 +1088,15 @@ pub fn parse_open_jsx_block(
                             attributes_markup_vector.push(format!("video=\"{value}\""));
                         }
                         "start" => {
-                            open_jsx_component_register.add_how_to_step_start(value);
+                            open_jsx_component_register
+                                .add_how_to_step_start(value)
+                                .map_err(|kind| LineError { input: value, kind })?;
                             attributes_markup_vector.push(format!("start={{{value}}}"));
                         }
                         "end" => {
-                            open_jsx_component_register.add_how_to_step_end(value);
+                            open_jsx_component_register
+                                .add_how_to_step_end(value)
+                                .map_err(|kind| LineError { input: value, kind })?;
                             attributes_markup_vector.push(format!("end={{{value}}}"));
                         }
                         &_ => {}
@@ -1035,7 +1118,6 @@ pub fn parse_open_jsx_block(
                 }
             }
             Err(_) => match alt((
-                form_fenced_code_block_first_line,
                 form_video_component_first_line,
                 form_how_to_section_component_last_line,
             ))
@@ -1054,12 +1136,7 @@ pub fn parse_open_jsx_block(
         Some(JSXComponentType::HowToStepOpening) => {
             match form_how_to_step_component_opening_line(line) {
                 Ok((_, (line, attributes, line_type, level))) => {
-                    let (_, attributes_vector) = parse_html_tag_attributes(attributes)
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "[ ERROR ] Unable to parse HowToDirection component props: {line}"
-                            )
-                        });
+                    let attributes_vector = parse_component_props(attributes, "HowToStep")?;
                     let mut attributes_markup_vector: Vec<String> = Vec::new();
                     for (key, value) in attributes_vector {
                         match key {
@@ -1076,11 +1153,15 @@ pub fn parse_open_jsx_block(
                                 attributes_markup_vector.push(format!("video=\"{value}\""));
                             }
                             "start" => {
-                                open_jsx_component_register.add_how_to_step_start(value);
+                                open_jsx_component_register
+                                    .add_how_to_step_start(value)
+                                    .map_err(|kind| LineError { input: value, kind })?;
                                 attributes_markup_vector.push(format!("start={{{value}}}"));
                             }
                             "end" => {
-                                open_jsx_component_register.add_how_to_step_end(value);
+                                open_jsx_component_register
+                                    .add_how_to_step_end(value)
+                                    .map_err(|kind| LineError { input: value, kind })?;
                                 attributes_markup_vector.push(format!("end={{{value}}}"));
                             }
                             &_ => {}
@@ -1103,10 +1184,7 @@ pub fn parse_open_jsx_block(
         Some(JSXComponentType::HowToStep) => match form_how_to_direction_component_first_line(line)
         {
             Ok((_, (line, attributes, line_type, level))) => {
-                let (_, attributes_vector) =
-                    parse_html_tag_attributes(attributes).unwrap_or_else(|_| {
-                        panic!("[ ERROR ] Unable to parse HowToDirection component props: {line}")
-                    });
+                let attributes_vector = parse_component_props(attributes, "HowToDirection")?;
                 match attributes_vector
                     .iter()
                     .find(|&&(key, _value)| key == "text")
@@ -1133,7 +1211,6 @@ pub fn parse_open_jsx_block(
                 }
             }
             Err(_) => match alt((
-                form_fenced_code_block_first_line,
                 form_video_component_first_line,
                 form_how_to_step_component_last_line,
             ))
@@ -1152,12 +1229,7 @@ pub fn parse_open_jsx_block(
         Some(JSXComponentType::HowToDirectionOpening) => {
             match form_how_to_direction_component_opening_line(line) {
                 Ok((_, (line, attributes, line_type, level))) => {
-                    let (_, attributes_vector) = parse_html_tag_attributes(attributes)
-                        .unwrap_or_else(|_| {
-                            panic!(
-                                "[ ERROR ] Unable to parse HowToDirection component props: {line}"
-                            )
-                        });
+                    let attributes_vector = parse_component_props(attributes, "HowToDirection")?;
                     match attributes_vector
                         .iter()
                         .find(|&&(key, _value)| key == "text")
@@ -1187,12 +1259,7 @@ pub fn parse_open_jsx_block(
         Some(JSXComponentType::HowToDirection) => {
             match form_how_to_direction_component_last_line(line) {
                 Ok((_, (line, line_type, level))) => Some((line, line_type, level)),
-                Err(_) => match alt((
-                    form_fenced_code_block_first_line,
-                    form_video_component_first_line,
-                ))
-                .parse(line)
-                {
+                Err(_) => match alt((form_video_component_first_line,)).parse(line) {
                     Ok((_, (line, line_type, level))) => {
                         if line.is_empty() {
                             None
@@ -1230,10 +1297,7 @@ pub fn parse_open_jsx_block(
                 if line.is_empty() {
                     None
                 } else {
-                    let (_, attributes_vector) = parse_html_tag_attributes(attributes)
-                        .unwrap_or_else(|_| {
-                            panic!("[ ERROR ] Unable to parse HowTo component props: {line}")
-                        });
+                    let attributes_vector = parse_component_props(attributes, "HowTo")?;
                     let how_to = open_jsx_component_register.how_to_mut();
                     if let Some(how_to_value) = how_to {
                         for (key, value) in attributes_vector {
@@ -1246,5 +1310,6 @@ pub fn parse_open_jsx_block(
             }
             Err(_) => None,
         },
-    }
+    };
+    Ok(result.map(|(markup, line_type, level)| (Block::Markup(markup), line_type, level)))
 }
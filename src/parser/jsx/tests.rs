@@ -1,14 +1,14 @@
 use crate::parser::{
+    ast::Block,
+    error::{LineError, ParseErrorKind},
     jsx::{
-        form_jsx_component_first_line, form_jsx_component_opening_line, parse_jsx_component,
-        parse_jsx_component_first_line, JSXTagType,
+        form_jsx_component_first_line, form_jsx_component_opening_line,
+        parse_image_component_index, parse_jsx_component, parse_jsx_component_first_line,
+        parse_open_jsx_block, JSXComponentRegister, JSXComponentType, JSXTagType,
     },
-    HTMLTagType,
-};
-use nom::{
-    error::{Error, ErrorKind},
-    Err,
+    HTMLTagType, LineType,
 };
+use nom::{error::ErrorKind, Err};
 
 #[test]
 pub fn test_form_jsx_component_first_line() {
@@ -43,7 +43,7 @@ pub fn test_form_jsx_component_first_line() {
     let mdx_line = "<ComponentPure />";
     assert_eq!(
         form_jsx_component_first_line(mdx_line, "Component"),
-        Err(Err::Error(Error::new("Pure", ErrorKind::Eof)))
+        Err(Err::Error(LineError::new("Pure", ErrorKind::Eof)))
     );
 
     let mdx_line = "<Component";
@@ -65,6 +65,16 @@ pub fn test_parse_jsx_component() {
     );
 }
 
+#[test]
+pub fn test_parse_image_component_index() {
+    assert_eq!(
+        parse_image_component_index("<Image index={12} caption=\"Agfa\"/>"),
+        Ok((" caption=\"Agfa\"/>", 12))
+    );
+    assert!(parse_image_component_index("<Image data-index={1}/>").is_err());
+    assert!(parse_image_component_index("<Image index={first}/>").is_err());
+}
+
 #[test]
 pub fn test_parse_jsx_component_first_line() {
     let mdx_line = "<CodeFragment";
@@ -102,3 +112,37 @@ pub fn test_form_jsx_component_opening_line() {
         ))
     );
 }
+
+#[test]
+pub fn test_parse_open_jsx_block() {
+    let mut register = JSXComponentRegister::new();
+    register.insert_prop("name", "Develop a Film");
+    register.add_how_to_section("Preparation");
+    register.push(JSXComponentType::HowTo);
+    register.push(JSXComponentType::HowToSection);
+
+    let mdx_line = "<HowToStep name=\"Load the tank\" start=\"90\">";
+    assert_eq!(
+        parse_open_jsx_block(mdx_line, &mut register),
+        Ok(Some((
+            Block::Markup(String::from(
+                "    <HowToStep {slug} position={1} section={1} name=\"Load the tank\" start={90}>"
+            )),
+            LineType::HowToStepOpen,
+            0
+        )))
+    );
+
+    // step times which are not whole numbers are reported, pointing at the value
+    let mdx_line = "<HowToStep name=\"Load the tank\" start=\"ninety\">";
+    assert_eq!(
+        parse_open_jsx_block(mdx_line, &mut register),
+        Err(LineError {
+            input: &mdx_line[39..45],
+            kind: ParseErrorKind::InvalidHowToStepTime(
+                String::from("start"),
+                String::from("ninety")
+            )
+        })
+    );
+}
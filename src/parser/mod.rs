@@ -1,35 +1,53 @@
 #[cfg(test)]
 mod tests;
 
+pub mod ast;
+pub mod emphasis;
+pub mod error;
+pub mod frontmatter;
 pub mod jsx;
+pub mod typography;
 use crate::{
+    parser::ast::{
+        Block, BlockQuote, CalloutType, CodeBlock, Document, DocumentBuilder, DocumentHeading,
+        Footnote, FootnoteDefinition, FootnoteReference, FrontmatterValue, HTMLBlockElementType,
+        Heading, HtmlBlock, Image, Inline, JsxComponent, Link, LinkDefinition, LinkReference, List,
+        ListItem, ListType, LocalImage, Table, TableAlign, TableRow,
+    },
+    parser::emphasis::{delimiter_run, process_emphasis, InlineToken},
+    parser::error::{IResult, LineError, ParseError, ParseErrorKind},
+    parser::frontmatter::{parse_frontmatter, parse_frontmatter_fields},
     parser::jsx::{
         form_code_fragment_component_first_line, form_gatsby_not_maintained_component,
         form_image_component, form_poll_component_first_line, form_questions_component,
-        form_tweet_component, form_video_component_first_line, parse_open_jsx_block,
-        JSXComponentRegister, JSXComponentType,
+        form_table_of_contents_component, form_tweet_component, form_video_component_first_line,
+        parse_image_component_index, parse_open_jsx_block, CodeFence, JSXComponentRegister,
+        JSXComponentType,
     },
+    parser::typography::{format_heading_typography, format_typography},
+    renderer::{render_document, AstroOutput},
     utility::stack::Stack,
 };
 use deunicode::deunicode;
 use markup_fmt::{config::FormatOptions, format_text, Language};
 use nom::{
     branch::alt,
-    bytes::complete::{is_not, tag, tag_no_case, take_until},
-    character::complete::{alpha1, alphanumeric1, digit1, multispace0, multispace1},
-    combinator::{opt, peek, recognize, rest, value},
-    error::{Error, ErrorKind},
-    multi::{many0, many0_count, many1, many1_count},
+    bytes::complete::{is_not, tag, tag_no_case, take_until, take_while1, take_while_m_n},
+    character::complete::{
+        alpha1, alphanumeric1, digit1, multispace0, multispace1, one_of, satisfy, space0, space1,
+    },
+    combinator::{eof, map, opt, peek, recognize, rest, value},
+    error::ErrorKind,
+    multi::{many0, many0_count, many1_count, many_m_n, separated_list1},
     sequence::{delimited, pair, preceded, separated_pair, terminated},
-    Err, IResult, Parser,
+    Err, Offset, Parser,
 };
 use std::{
-    borrow::Cow,
     collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufRead, BufReader, Cursor, Read, Seek, Write},
+    fs::{self, File},
+    io::Write,
     path::Path,
-    time::Instant,
+    str::FromStr,
 };
 
 type ParsedFencedCodeBlockMeta<'a> = (
@@ -41,16 +59,6 @@ type ParsedFencedCodeBlockMeta<'a> = (
     Option<bool>,    //collapse
 );
 
-#[derive(PartialEq)]
-enum HTMLBlockElementType {
-    Comment,
-    DescriptionList,
-    Div,
-    Figure,
-    TableBody,
-    TableHead,
-}
-
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum HTMLTagType {
     Opening,
@@ -66,11 +74,13 @@ pub enum LineType {
     CodeFragmentOpening,
     FencedCodeBlock,
     FencedCodeBlockOpen,
+    FootnoteDefinitionOpen,
     Frontmatter,
     FrontmatterDelimiter,
     GatsbyNotMaintained,
     JSXComponent,
     Heading,
+    LinkReferenceDefinition,
     HTMLBlockLevelComment,
     HTMLBlockLevelCommentOpen,
     HTMLDescriptionList,
@@ -95,13 +105,14 @@ pub enum LineType {
     HowToDirectionOpen,
     HowToDirectionOpening,
     Image,
-    OrderedList,
     OrderedListItemOpen,
     Paragraph,
     Poll,
     PollOpen,
     PollOpening,
     Questions,
+    TableOfContents,
+    ThematicBreak,
     Tweet,
     UnorderedListItem,
     Video,
@@ -109,22 +120,14 @@ pub enum LineType {
     VideoOpening,
 }
 
-#[derive(Debug, PartialEq)]
-enum ListType {
-    Ordered,
-    Unordered,
-}
-
-#[derive(Debug, PartialEq)]
-enum MarkdownBlock {
-    OrderedList,
-}
-
-#[derive(Clone, Debug, PartialEq)]
-enum TableAlign {
-    Centre,
-    Left,
-    Right,
+/* list being parsed, with its bullet or ordered list delimiter, and the indentation of its markers
+ * and of the content of its last item
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OpenList {
+    marker: char,
+    indentation: usize,
+    content_indentation: usize,
 }
 
 #[allow(dead_code)]
@@ -132,114 +135,39 @@ fn discard_leading_whitespace(line: &str) -> IResult<&str, &str> {
     preceded(multispace0, rest).parse(line)
 }
 
-fn escape_code(line: &str) -> String {
-    line.replace('<', "\\u003C")
-        .replace('>', "\\u003E")
-        .replace('`', "\\u0060")
-        .replace('{', "\\u007B")
-        .replace('}', "\\u007D")
-        .replace("import.", "import..")
-        .replace("process.env", "process..env")
-}
-
 fn remove_html_tags(line: &str) -> IResult<&str, &str> {
     let (remaining_line, initial_segment) = take_until("<")(line)?;
     let (final_segment, _) = parse_self_closing_html_tag(remaining_line)?;
     Ok((final_segment, initial_segment))
 }
 
-fn form_code_span_html_string(input: &str) -> String {
-    match segment_code_span_line(input) {
-        Ok((_, (initial_segment, code_segment, final_segment))) => {
-            format!(
-                "{initial_segment}<code>{code_segment}</code>{}",
-                form_code_span_html_string(final_segment)
-            )
-        }
-        Err(_) => String::from(input),
-    }
-}
-
-/* if the last word of the title is shorter than 6 characters, replaces the last space with a
- * non-breaking space
- */
-fn format_heading_widows(heading: &str) -> String {
-    match heading.rsplit_once(' ') {
-        Some((before_space, after_space)) => {
-            if after_space.len() < 5 {
-                format!(
-                    "{}\\u00a0{}",
-                    format_heading(before_space),
-                    format_heading(after_space)
-                )
-            } else {
-                format_heading(heading).to_string()
-            }
+// code spans and backslash escapes are the only inline markup recognised in headings
+fn parse_heading_inline_text(input: &str) -> Vec<Inline> {
+    let mut result: Vec<Inline> = Vec::new();
+    let mut remaining_input = input;
+    while let Some(index) = remaining_input.find(['`', '\\']) {
+        let (initial_segment, tagged_segment) = remaining_input.split_at(index);
+        push_inline_text(&mut result, initial_segment);
+        if let Ok((final_segment, escaped)) = parse_backslash_escape(tagged_segment) {
+            result.push(Inline::Text(escaped));
+            remaining_input = final_segment;
+        } else if let Ok((final_segment, code)) = parse_code_span(tagged_segment) {
+            result.push(Inline::Code(code.to_string()));
+            remaining_input = final_segment;
+        } else {
+            // an unmatched backtick run, or a backslash which escapes nothing, is text
+            let tag_length = backtick_run_length(tagged_segment).max(1);
+            push_inline_text(&mut result, &tagged_segment[..tag_length]);
+            remaining_input = &tagged_segment[tag_length..];
         }
-        None => format_heading(heading).to_string(),
     }
+    push_inline_text(&mut result, remaining_input);
+    merge_inline_text(result)
 }
 
-fn format_heading<'a, I: Into<Cow<'a, str>>>(heading: I) -> Cow<'a, str> {
-    fn is_replace_character(c: char) -> bool {
-        c == '-' || c == '\'' || c == '"'
-    }
-
-    let heading = heading.into();
-    let first = heading.find(is_replace_character);
-    if let Some(first) = first {
-        let (mut result, rest) = match first {
-            0 => match &heading[0..1] {
-                "\"" => (String::from("\\u201c"), heading[1..].chars()),
-                "'" => (String::from("\\u2018"), heading[1..].chars()),
-                _ => (String::from(&heading[0..first]), heading[first..].chars()),
-            },
-            _ => {
-                if &heading[(first - 1)..first] == " " {
-                    (
-                        String::from(&heading[0..(first - 1)]),
-                        heading[(first - 1)..].chars(),
-                    )
-                } else {
-                    (String::from(&heading[0..first]), heading[first..].chars())
-                }
-            }
-        };
-        result.reserve(heading.len() - first);
-
-        let mut preceded_by_space = false;
-        for c in rest {
-            match c {
-                '-' => result.push_str("&#x2011;"), // non-breaking hyphen
-                ' ' => {
-                    preceded_by_space = true;
-                    result.push(c);
-                }
-                '\'' => {
-                    if preceded_by_space {
-                        preceded_by_space = false;
-                        result.push_str("\\u2018");
-                    } else {
-                        result.push_str("\\u2019");
-                    }
-                }
-                '"' => {
-                    if preceded_by_space {
-                        preceded_by_space = false;
-                        result.push_str("\\u201c");
-                    } else {
-                        result.push_str("\\u201d");
-                    }
-                }
-                _ => {
-                    preceded_by_space = false;
-                    result.push(c);
-                }
-            }
-        }
-        Cow::Owned(result)
-    } else {
-        heading
+fn push_inline_text(inlines: &mut Vec<Inline>, text: &str) {
+    if !text.is_empty() {
+        inlines.push(Inline::Text(text.to_string()));
     }
 }
 
@@ -254,7 +182,7 @@ fn slugify_title(title: &str) -> String {
         let deunicoded_title = deunicode(title);
         let mut result = String::with_capacity(deunicoded_title.len());
         let mut last_was_replaced = true;
-        let remove_characters = "?'`:[]()";
+        let remove_characters = "?'`:[]()\\";
         let replace_characters = " -/.,$"; // include '-' here to avoid "--" in result
         for chars in deunicoded_title.chars() {
             if replace_characters.contains(chars) {
@@ -281,14 +209,6 @@ pub fn author_name_from_cargo_pkg_authors() -> &'static str {
     result
 }
 
-// consumes delimiter
-fn parse_up_to_inline_wrap_segment<'a>(
-    line: &'a str,
-    delimiter: &'a str,
-) -> IResult<&'a str, (&'a str, &'a str)> {
-    separated_pair(take_until(delimiter), tag(delimiter), rest).parse(line)
-}
-
 fn parse_html_tag_attributes_str(line: &str) -> IResult<&str, &str> {
     is_not(">/")(line)
 }
@@ -416,25 +336,67 @@ fn segment_anchor_element_no_attributes_line(line: &str) -> IResult<&str, (&str,
     ))
 }
 
-fn segment_code_span_line(line: &str) -> IResult<&str, (&str, &str, &str)> {
-    let delimiter = "`";
-    let (_, (initial_segment, remainder)) = parse_up_to_inline_wrap_segment(line, delimiter)?;
-    let (_, (bold_segment, final_segment)) = parse_inline_wrap_segment(remainder, delimiter)?;
-    Ok(("", (initial_segment, bold_segment, final_segment)))
+fn backtick_run_length(line: &str) -> usize {
+    line.len() - line.trim_start_matches('`').len()
 }
 
-fn segment_emphasis_line(line: &str) -> IResult<&str, (&str, &str, &str)> {
-    let delimiter = "*";
-    let (_, (initial_segment, remainder)) = parse_up_to_inline_wrap_segment(line, delimiter)?;
-    let (_, (bold_segment, final_segment)) = parse_inline_wrap_segment(remainder, delimiter)?;
-    Ok(("", (initial_segment, bold_segment, final_segment)))
+/* a code span closes on the next backtick run of the same length as the opening one, and a single
+ * space is stripped from each end, so code may start or end with a backtick
+ */
+fn parse_code_span(line: &str) -> IResult<&str, &str> {
+    let opening_length = backtick_run_length(line);
+    if opening_length == 0 {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Tag)));
+    }
+    let content = &line[opening_length..];
+    let mut position = 0;
+    while let Some(index) = content[position..].find('`') {
+        let start = position + index;
+        let closing_length = backtick_run_length(&content[start..]);
+        if closing_length == opening_length {
+            let code = &content[..start];
+            let is_padded = |c: char| c == ' ' || c == '\n';
+            let code = if code.starts_with(is_padded)
+                && code.ends_with(is_padded)
+                && !code.trim_matches(is_padded).is_empty()
+            {
+                &code[1..code.len() - 1]
+            } else {
+                code
+            };
+            return Ok((&content[start + closing_length..], code));
+        }
+        position = start + closing_length;
+    }
+    Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)))
+}
+
+// a backslash before ASCII punctuation makes it literal, with HTML and JSX syntax characters escaped
+fn parse_backslash_escape(line: &str) -> IResult<&str, String> {
+    let (remaining_line, character) =
+        preceded(tag("\\"), satisfy(|c| c.is_ascii_punctuation())).parse(line)?;
+    let escaped = match character {
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        '&' => String::from("&amp;"),
+        '{' => String::from("&#123;"),
+        '}' => String::from("&#125;"),
+        other => other.to_string(),
+    };
+    Ok((remaining_line, escaped))
 }
 
-fn segment_strong_emphasis_line(line: &str) -> IResult<&str, (&str, &str, &str)> {
-    let delimiter = "**";
-    let (_, (initial_segment, remainder)) = parse_up_to_inline_wrap_segment(line, delimiter)?;
-    let (_, (bold_segment, final_segment)) = parse_inline_wrap_segment(remainder, delimiter)?;
-    Ok(("", (initial_segment, bold_segment, final_segment)))
+// removes backslashes escaping ASCII punctuation, in link destinations and titles
+fn unescape_backslashes(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut characters = value.chars().peekable();
+    while let Some(character) = characters.next() {
+        match characters.peek() {
+            Some(next) if character == '\\' && next.is_ascii_punctuation() => {}
+            _ => result.push(character),
+        }
+    }
+    result
 }
 
 fn parse_html_tag_attribute(line: &str) -> IResult<&str, (&str, &str)> {
@@ -467,11 +429,11 @@ fn parse_html_tag_attributes(attributes: &str) -> IResult<&str, Vec<(&str, &str)
     .parse(attributes)
 }
 
-fn parse_href_scheme(href: &str) -> IResult<&str, &str> {
+pub fn parse_href_scheme(href: &str) -> IResult<&str, &str> {
     alt((tag_no_case("HTTP://"), tag_no_case("HTTPS://"))).parse(href)
 }
 
-fn form_html_anchor_element_line(line: &str) -> IResult<&str, String> {
+fn form_html_anchor_element_line(line: &str) -> IResult<&str, Vec<Inline>> {
     let (_, (initial_segment, anchor_attributes_segment, final_segment)) = alt((
         segment_anchor_element_with_attributes_line,
         segment_anchor_element_no_attributes_line,
@@ -481,1028 +443,2737 @@ fn form_html_anchor_element_line(line: &str) -> IResult<&str, String> {
     let (remaining_line, link_content) = take_until("</a>")(final_segment)?;
 
     let attributes_hash_map: HashMap<&str, &str> = attributes_vector.into_iter().collect();
-    let href = attributes_hash_map
-        .get("href")
-        .unwrap_or_else(|| panic!("[ ERROR ] Anchor tag missing href: {line}"));
-    let external_site = parse_href_scheme(href).is_ok();
-    let mut additional_attributes = String::new();
-
-    if external_site {
-        if !attributes_hash_map.contains_key("target") {
-            additional_attributes.push_str(" target=\"_blank\"");
-        }
-        if !attributes_hash_map.contains_key("rel") {
-            additional_attributes.push_str(" rel=\"nofollow noopener noreferrer\"");
-        }
-    }
-    let icon = if external_site {
-        "&nbsp;<LinkIcon />"
-    } else {
-        ""
+    let Some(href) = attributes_hash_map.get("href") else {
+        let opening_tag = &line[initial_segment.len()..line.len() - final_segment.len()];
+        return Err(LineError::failure(opening_tag, ParseErrorKind::MissingHref));
     };
     let (remaining_line, (tag_name, _, _)) = parse_closing_html_tag(remaining_line)?;
     match tag_name {
         "a" => {
-            let (_, link_content) = parse_inline_wrap_text(link_content)?;
-            Ok((
-        remaining_line,
-        format!("{initial_segment}<a {anchor_attributes_segment}{additional_attributes}>{link_content}{icon}</a>"),
-    ))
+            let (_, content) = parse_inline_wrap_text(link_content)?;
+            let content = remove_nested_links(content);
+            let mut result: Vec<Inline> = Vec::new();
+            push_inline_text(&mut result, initial_segment);
+            result.push(Inline::Link(Link {
+                href: href.to_string(),
+                attributes: anchor_attributes_segment.to_string(),
+                has_rel: attributes_hash_map.contains_key("rel"),
+                has_target: attributes_hash_map.contains_key("target"),
+                content,
+            }));
+            Ok((remaining_line, result))
         }
-        _ => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+        _ => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
     }
 }
 
-fn form_code_span_line(line: &str) -> IResult<&str, String> {
-    let (_, (initial_segment, code_segment, final_segment)) = segment_code_span_line(line)?;
-    Ok((
-        final_segment,
-        format!(
-            "{initial_segment}<InlineCodeFragment code={{`{}`}} />",
-            escape_code(code_segment)
-        ),
-    ))
-}
-
-fn parse_fenced_code_block_first_line(line: &str) -> IResult<&str, ParsedFencedCodeBlockMeta<'_>> {
-    let (meta, _) = tag("```")(line)?;
-    let (remaining_meta, language_option) =
-        opt(alt((terminated(take_until(" "), tag(" ")), alpha1))).parse(meta)?;
-    let (remaining_meta, first_line_number_option) =
-        opt(alt((terminated(digit1, tag(" ")), digit1))).parse(remaining_meta)?;
-    let (remaining_meta, highlight_lines_option) = opt(alt((
-        delimited(peek(tag("{")), is_not(" \t\r\n"), tag(" ")),
-        preceded(peek(tag("{")), is_not(" \t\r\n")),
-    )))
-    .parse(remaining_meta)?;
-    let (remaining_meta, title_option) = opt(alt((
-        delimited(tag("\""), take_until("\" "), tag("\" ")),
-        delimited(tag("\""), take_until("\""), tag("\"")),
-    )))
-    .parse(remaining_meta)?;
-    let (remaining_meta, caption_option) = opt(alt((
-        delimited(tag("["), take_until("] "), tag("] ")),
-        delimited(tag("["), take_until("]"), tag("]")),
-    )))
-    .parse(remaining_meta)?;
-    let (_, collapse_option_tag) = opt(tag("<>")).parse(remaining_meta)?;
-    let collapse_option = match collapse_option_tag {
-        Some("<>") => Some(true),
-        _ => Some(false),
-    };
-    Ok((
-        "",
-        (
-            language_option,
-            first_line_number_option,
-            highlight_lines_option,
-            title_option,
-            caption_option,
-            collapse_option,
-        ),
-    ))
-}
-
-fn parse_fenced_code_block_last_line(line: &str) -> IResult<&str, &str> {
-    tag("```")(line)
-}
-
-fn parse_html_block_level_comment_first_line(line: &str) -> IResult<&str, &str> {
-    tag("<!--")(line)
+// link text runs to the matching closing bracket, so may itself contain brackets or code spans
+fn parse_link_text(line: &str) -> IResult<&str, &str> {
+    let (text, _) = tag("[")(line)?;
+    let mut depth = 0;
+    let mut in_code_span = false;
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if !in_code_span => escaped = true,
+            '`' => in_code_span = !in_code_span,
+            '[' if !in_code_span => depth += 1,
+            ']' if !in_code_span => {
+                if depth == 0 {
+                    return Ok((&text[index + 1..], &text[..index]));
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)))
 }
 
-fn parse_html_block_level_comment_last_line(line: &str) -> IResult<&str, &str> {
-    terminated(take_until("-->"), tag("-->")).parse(line)
+// any parentheses in a destination, not wrapped in angle brackets, must be balanced
+fn parse_bare_link_destination(line: &str) -> IResult<&str, &str> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => return Ok((&line[index..], &line[..index])),
+            ')' => depth -= 1,
+            _ if c.is_whitespace() => return Ok((&line[index..], &line[..index])),
+            _ => {}
+        }
+    }
+    if depth == 0 {
+        Ok(("", line))
+    } else {
+        Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)))
+    }
 }
 
-fn parse_table_column_alignment(line: &str) -> IResult<&str, TableAlign> {
-    let (remaining_line, cell) =
-        terminated(take_until("|"), pair(tag("|"), multispace0)).parse(line)?;
-    let (_, alignment) = alt((
-        value(
-            TableAlign::Centre,
-            delimited(tag(":"), tag("---"), tag(":")),
-        ),
-        value(TableAlign::Left, preceded(tag(":"), tag("---"))),
-        value(TableAlign::Right, terminated(tag("---"), tag(":"))),
+fn parse_link_destination(line: &str) -> IResult<&str, &str> {
+    alt((
+        delimited(tag("<"), is_not(">\n"), tag(">")),
+        parse_bare_link_destination,
     ))
-    .parse(cell)?;
-    Ok((remaining_line, alignment))
+    .parse(line)
 }
 
-fn parse_table_cell(line: &str) -> IResult<&str, &str> {
-    terminated(take_until("|"), pair(tag("|"), multispace0)).parse(line)
+// title content up to the closing delimiter, skipping any escaped with a backslash
+fn parse_link_title_content(closing: char) -> impl Fn(&str) -> IResult<&str, &str> {
+    move |line: &str| {
+        let mut escaped = false;
+        for (index, c) in line.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if c == closing => return Ok((&line[index..], &line[..index])),
+                _ => {}
+            }
+        }
+        Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)))
+    }
 }
 
-// parses row separating header and body containing alignment markers
-fn parse_table_header_row(line: &str) -> IResult<&str, Vec<TableAlign>> {
-    let (headings, _) = preceded(tag("|"), multispace1).parse(line)?;
-    many1(parse_table_column_alignment).parse(headings)
+fn parse_link_title(line: &str) -> IResult<&str, &str> {
+    preceded(
+        multispace1,
+        alt((
+            delimited(tag("\""), parse_link_title_content('"'), tag("\"")),
+            delimited(tag("'"), parse_link_title_content('\''), tag("'")),
+            delimited(tag("("), parse_link_title_content(')'), tag(")")),
+        )),
+    )
+    .parse(line)
 }
 
-fn parse_table_line(line: &str) -> IResult<&str, Vec<&str>> {
-    let (headings, _) = preceded(tag("|"), multispace1).parse(line)?;
-    many1(parse_table_cell).parse(headings)
+// parses `[text](destination "title")`, returning text, destination and title
+fn parse_inline_link(line: &str) -> IResult<&str, (&str, &str, Option<&str>)> {
+    let (remaining_line, text) = parse_link_text(line)?;
+    let (remaining_line, (destination, title)) = delimited(
+        pair(tag("("), multispace0),
+        pair(parse_link_destination, opt(parse_link_title)),
+        pair(multispace0, tag(")")),
+    )
+    .parse(remaining_line)?;
+    Ok((remaining_line, (text, destination, title)))
 }
 
-fn form_html_block_element_first_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_remaining_line, (tag_name, _tag_attributes, _tag_type)) = parse_opening_html_tag(line)?;
-    match tag_name {
-        "dl" => Ok((
-            "",
-            (String::from(line), LineType::HTMLDescriptionListOpen, 0),
-        )),
-        "div" => Ok(("", (String::from(line), LineType::HTMLDivBlockOpen, 0))),
-        "figure" => Ok(("", (String::from(line), LineType::HTMLFigureBlockOpen, 0))),
-        _ => panic!("[ ERROR ] Unrecognised HTML block element: {tag_name}"),
+/* GFM extended autolink: a bare URL runs to the next space or `<`, less any trailing punctuation
+ * and unmatched closing parentheses
+ */
+fn parse_autolink_url(line: &str) -> IResult<&str, &str> {
+    let (after_scheme, _) = parse_href_scheme(line)?;
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(line.len());
+    let mut url = &line[..end];
+    loop {
+        let trimmed = url.trim_end_matches(['?', '!', '.', ',', ':', '*', '_', '~', '\'', '"']);
+        let trimmed = if trimmed.ends_with(')')
+            && trimmed.matches(')').count() > trimmed.matches('(').count()
+        {
+            &trimmed[..trimmed.len() - 1]
+        } else {
+            trimmed
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
     }
-}
-
-fn form_html_block_element_last_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_remaining_line, (tag_name, _tag_attributes, _tag_type)) = parse_closing_html_tag(line)?;
-    match tag_name {
-        "dl" => Ok(("", (String::from(line), LineType::HTMLDescriptionList, 0))),
-        "div" => Ok(("", (String::from(line), LineType::HTMLDivBlock, 0))),
-        "figure" => Ok(("", (String::from(line), LineType::HTMLFigureBlock, 0))),
-        _ => Err(Err::Error(Error::new(line, ErrorKind::Tag))),
+    if url.len() <= line.len() - after_scheme.len() {
+        return Err(Err::Error(LineError::new(line, ErrorKind::TakeWhile1)));
     }
-}
-
-fn form_fenced_code_block_first_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (
-        _,
-        (
-            language_option,
-            first_line_number_option,
-            highlight_line_numbers_option,
-            title_option,
-            caption_option,
-            collapse_option,
+    Ok((&line[url.len()..], url))
+}
+
+// position of the first bare URL, which must start the line or follow a space or opening delimiter
+fn find_autolink(line: &str) -> Option<usize> {
+    let lower_case_line = line.to_ascii_lowercase();
+    lower_case_line
+        .match_indices("http")
+        .map(|(index, _)| index)
+        .find(|index| {
+            let preceded_by_delimiter = line[..*index]
+                .chars()
+                .next_back()
+                .map_or(true, |c| c.is_whitespace() || "*_~(".contains(c));
+            preceded_by_delimiter && parse_autolink_url(&line[*index..]).is_ok()
+        })
+}
+
+fn form_autolink_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, url) = alt((
+        delimited(
+            tag("<"),
+            recognize(pair(parse_href_scheme, is_not(" <>"))),
+            tag(">"),
         ),
-    ) = parse_fenced_code_block_first_line(line)?;
-
-    let mut markup = String::from("<CodeFragment\n  client:visible");
-    if let Some(value) = language_option {
-        markup.push_str("\n  language=\"");
-        markup.push_str(value);
-        markup.push('\"');
-    };
-    if let Some(value) = first_line_number_option {
-        markup.push_str("\n  firstLine={");
-        markup.push_str(value);
-        markup.push('}');
-    };
-    if let Some(value) = highlight_line_numbers_option {
-        markup.push_str("\n  highlightLines={`");
-        markup.push_str(value);
-        markup.push_str("`}");
-    };
-    if let Some(value) = title_option {
-        markup.push_str("\n  title=\"");
-        markup.push_str(value);
-        markup.push('\"');
-    };
-    if let Some(value) = caption_option {
-        markup.push_str("\n  caption=\"");
-        markup.push_str(value);
-        markup.push('\"');
-    };
-    if let Some(true) = collapse_option {
-        markup.push_str("\n  collapse");
-    };
-    markup.push_str("\n  code={`");
-    Ok(("", (markup, LineType::FencedCodeBlockOpen, 0)))
+        parse_autolink_url,
+    ))
+    .parse(line)?;
+    let content = vec![Inline::Text(url.to_string())];
+    Ok((
+        final_segment,
+        vec![Inline::Link(markdown_link(url, None, content))],
+    ))
 }
 
-fn form_table_body_row(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_, cells) = parse_table_line(line)?;
-    let mut markup = String::from("    <tr>");
-    for cell in cells {
-        markup.push_str("\n      <td>");
-        markup.push_str(cell.trim_end());
-        markup.push_str("</td>");
+// links cannot contain other links, so any autolink in link text is kept as plain text
+fn remove_nested_links(content: Vec<Inline>) -> Vec<Inline> {
+    let mut result: Vec<Inline> = Vec::with_capacity(content.len());
+    for inline in content {
+        match inline {
+            Inline::Link(Link { content, .. }) => result.append(&mut remove_nested_links(content)),
+            other => result.push(other),
+        }
     }
-    markup.push_str("\n    </tr>");
-    Ok(("", (markup, LineType::HTMLTableBodyOpen, 0)))
+    merge_inline_text(result)
 }
 
-// regular row in table head
-fn form_table_head_row(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_, cells) = parse_table_line(line)?;
-    let mut markup = String::from("    <tr>");
-    for cell in cells {
-        markup.push_str("\n      <th scope=\"col\">");
-        markup.push_str(cell);
-        markup.push_str("</th>");
+// link from Markdown syntax, either inline or resolved from a link reference definition
+fn markdown_link(href: &str, title: Option<&str>, content: Vec<Inline>) -> Link {
+    let href = unescape_backslashes(href);
+    let mut attributes = format!("href=\"{href}\"");
+    if let Some(value) = title {
+        attributes.push_str(&format!(
+            " title=\"{}\"",
+            unescape_backslashes(value).replace('"', "&quot;")
+        ));
+    }
+    Link {
+        href,
+        attributes,
+        has_rel: false,
+        has_target: false,
+        content,
     }
-    markup.push_str("\n    </tr>");
-    Ok(("", (markup, LineType::HTMLTableHeadOpen, 0)))
 }
 
-// special row between head and body with alignment markers
-fn form_table_header_row(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    parse_table_header_row(line)?;
+// labels match case-insensitively, with runs of whitespace treated as a single space
+fn normalise_link_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn form_markdown_link_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, (text, href, title)) = parse_inline_link(line)?;
+    let (_, content) = parse_inline_wrap_text(text)?;
+    let content = remove_nested_links(content);
     Ok((
-        "",
-        (
-            String::from("  </thead>\n  <tbody>"),
-            LineType::HTMLTableBodyOpen,
-            0,
-        ),
+        final_segment,
+        vec![Inline::Link(markdown_link(href, title, content))],
     ))
 }
 
-fn form_table_body_last_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    match form_table_body_row(line) {
-        Ok(value) => Ok(value),
-        Err(_) => Ok((
-            "",
-            (
-                String::from("  </tbody>\n</table>"),
-                LineType::HTMLTableBody,
-                0,
-            ),
-        )),
+/* parses `![alt](src "title")`, failing when there is no alt text, local or remote; an empty alt
+ * is far more often forgotten than meant, so decorative images are written as HTML `<img alt="" />`,
+ * which passes through as is, making the choice explicit
+ */
+fn form_markdown_image_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, (alt, src, title)) = preceded(tag("!"), parse_inline_link).parse(line)?;
+    if alt.trim().is_empty() {
+        return Err(LineError::failure(
+            &line[..line.len() - final_segment.len()],
+            ParseErrorKind::MissingAltText,
+        ));
     }
-}
-
-fn form_table_head_first_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_, (row_body, line_type, indentation)) = form_table_head_row(line)?;
-    let markup = String::from("<table>\n  <thead>");
     Ok((
-        "",
-        (format!("{markup}\n{row_body}"), line_type, indentation),
+        final_segment,
+        vec![Inline::Image(Image {
+            alt: alt.to_string(),
+            src: src.to_string(),
+            title: title.map(unescape_backslashes),
+            local: None,
+        })],
     ))
 }
 
-// optimistically try to end the head section or alternatively add additional head line
-fn form_table_head_last_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    alt((form_table_header_row, form_table_head_row)).parse(line)
-}
-
-fn form_fenced_code_block_last_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_final_segment, _initial_segment) = parse_fenced_code_block_last_line(line)?;
-    Ok(("", (String::from("  `} />"), LineType::FencedCodeBlock, 0)))
-}
-
-fn form_emphasis_line(line: &str) -> IResult<&str, String> {
-    let (_, (initial_segment, bold_segment, final_segment)) = segment_emphasis_line(line)?;
+// parses `[text][label]`, `[label][]` or `[label]`, leaving the label to be resolved later
+fn form_reference_link_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, text) = parse_link_text(line)?;
+    let (final_segment, label) = opt(alt((
+        value("", tag("[]")),
+        delimited(tag("["), is_not("[]"), tag("]")),
+    )))
+    .parse(final_segment)?;
+    let (_, content) = parse_inline_wrap_text(text)?;
+    let shortcut = label.is_none();
+    let label = match label {
+        Some("") | None => text,
+        Some(value) => value,
+    };
     Ok((
         final_segment,
-        format!("{initial_segment}<em>{bold_segment}</em>"),
+        vec![Inline::LinkReference(LinkReference {
+            label: normalise_link_label(label),
+            shortcut,
+            source: line[..line.len() - final_segment.len()].to_string(),
+            content,
+        })],
     ))
 }
 
-fn form_strong_emphasis_line(line: &str) -> IResult<&str, String> {
-    let (_, (initial_segment, bold_segment, final_segment)) = segment_strong_emphasis_line(line)?;
-    match form_code_span_line(bold_segment) {
-        Ok((_, code_segment)) => Ok((
-            final_segment,
-            format!("{initial_segment}<strong>{code_segment}</strong>"),
-        )),
-        Err(_) => Ok((
-            final_segment,
-            format!("{initial_segment}<strong>{bold_segment}</strong>"),
-        )),
-    }
+// parses `[^label]: text`, returning the normalised label and the first line of text
+fn parse_footnote_definition(line: &str) -> IResult<&str, String> {
+    let (text, (_, label, _)) = (
+        many_m_n(0, 3, tag(" ")),
+        delimited(
+            tag("["),
+            recognize(preceded(tag("^"), is_not("] \t"))),
+            tag("]:"),
+        ),
+        multispace0,
+    )
+        .parse(line)?;
+    Ok((text, normalise_link_label(label)))
 }
 
-fn form_inline_wrap_text_number_range(line: &str) -> IResult<&str, String> {
-    let (remaining_line, first_tag) = recognize(separated_pair(
-        tag("<InlineCodeFragment code={`"),
-        digit1,
-        tag("`} />"),
-    ))
-    .parse(line)?;
-    let (remaining_line, _) = alt((tag("&ndash;"), tag("-"))).parse(remaining_line)?;
-    let (remaining_line, second_tag) = recognize(separated_pair(
-        tag("<InlineCodeFragment code={`"),
-        digit1,
-        tag("`} />"),
-    ))
-    .parse(remaining_line)?;
-
+fn form_footnote_definition_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_, label) = parse_footnote_definition(line)?;
+    let definition = FootnoteDefinition {
+        label,
+        children: Vec::new(),
+    };
     Ok((
-        remaining_line,
-        format!("{first_tag}&thinsp;&ndash;&thinsp;{second_tag}"),
+        "",
+        (
+            Block::FootnoteDefinition(definition),
+            LineType::FootnoteDefinitionOpen,
+            0,
+        ),
     ))
 }
 
-fn form_inline_wrap_inline_code_fragment(line: &str) -> IResult<&str, String> {
-    let (remaining_line, tag_content) = delimited(
-        tag("<InlineCodeFragment code={`"),
-        take_until("/>"),
-        tag("/>"),
+// parses `[label]: destination "title"`, which may appear on any line of the document
+fn parse_link_reference_definition(line: &str) -> IResult<&str, (String, LinkDefinition)> {
+    let (remaining_line, (_, label, _, _, href, title, _, _)) = (
+        many_m_n(0, 3, tag(" ")),
+        parse_link_text,
+        tag(":"),
+        multispace0,
+        parse_link_destination,
+        opt(parse_link_title),
+        multispace0,
+        eof,
     )
-    .parse(line)?;
-
+        .parse(line)?;
+    // labels starting with a caret are footnotes
+    if href.is_empty() || label.trim().is_empty() || label.starts_with('^') {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Verify)));
+    }
     Ok((
         remaining_line,
-        format!("<InlineCodeFragment code={{`{tag_content}/>"),
+        (
+            normalise_link_label(label),
+            LinkDefinition {
+                href: href.to_string(),
+                title: title.map(String::from),
+            },
+        ),
     ))
 }
 
-fn format_inline_wrap_text_number_range(line: &str) -> IResult<&str, String> {
-    let (remaining_line, initial_part) = take_until("<InlineCodeFragment")(line)?;
-    let (remaining_line, inline_code_fragment_part) = alt((
-        form_inline_wrap_text_number_range,
-        form_inline_wrap_inline_code_fragment,
+fn form_link_reference_definition_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    parse_link_reference_definition(line)?;
+    Ok((
+        "",
+        (
+            Block::Markup(String::from(line)),
+            LineType::LinkReferenceDefinition,
+            0,
+        ),
     ))
-    .parse(remaining_line)?;
-
-    match format_inline_wrap_text_number_range(remaining_line) {
-        Ok((_, value)) => Ok((
-            "",
-            format!("{initial_part}{inline_code_fragment_part}{value}"),
-        )),
-        Err(_) => Ok((
-            "",
-            format!("{initial_part}{inline_code_fragment_part}{remaining_line}"),
-        )),
-    }
 }
 
-fn parse_inline_wrap_text(line: &str) -> IResult<&str, String> {
-    fn is_wrap_tag(c: char) -> bool {
-        c == '`' || c == '*' || c == '<'
+// references written as `[text][label]` or `[label][]`, which must have a definition
+fn collect_link_references(inlines: &[Inline], references: &mut Vec<LinkReference>) {
+    for inline in inlines {
+        match inline {
+            Inline::LinkReference(reference) => {
+                if !reference.shortcut {
+                    references.push(reference.clone());
+                }
+                collect_link_references(&reference.content, references);
+            }
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content) => {
+                collect_link_references(content, references);
+            }
+            Inline::Link(link) => collect_link_references(&link.content, references),
+            Inline::Code(_)
+            | Inline::FootnoteReference(_)
+            | Inline::Image(_)
+            | Inline::LineBreak
+            | Inline::SoftBreak
+            | Inline::Text(_) => {}
+        }
     }
+}
 
-    let first_tag = line.find(is_wrap_tag);
-    if let Some(first_tag) = first_tag {
-        let line_from_tag = &line[first_tag..];
-        let parsed_result = match &line_from_tag[0..1] {
-            "`" => form_code_span_line(line_from_tag),
-            "<" => form_html_anchor_element_line(line_from_tag),
-            "*" => alt((form_strong_emphasis_line, form_emphasis_line)).parse(line_from_tag),
-            _ => return Ok(("", line.to_string())),
-        };
-        let Ok((final_segment, initial_segment)) = parsed_result else {
-            return Ok(("", line.to_string()));
-        };
-        let (_, final_final_segment) = parse_inline_wrap_text(final_segment)?;
-        let line_before_tag = &line[..first_tag];
-        Ok((
-            "",
-            format!("{line_before_tag}{initial_segment}{final_final_segment}"),
-        ))
-    } else {
-        Ok(("", line.to_string()))
+/* moves footnote definitions, from anywhere in the document, into `definitions`, keeping the first
+ * definition of each label
+ */
+fn take_footnote_definitions(
+    blocks: &mut Vec<Block>,
+    definitions: &mut HashMap<String, Vec<Block>>,
+) {
+    let mut index = 0;
+    while index < blocks.len() {
+        match &mut blocks[index] {
+            Block::FootnoteDefinition(_) => {
+                if let Block::FootnoteDefinition(FootnoteDefinition {
+                    label,
+                    mut children,
+                }) = blocks.remove(index)
+                {
+                    take_footnote_definitions(&mut children, definitions);
+                    definitions.entry(label).or_insert(children);
+                }
+                continue;
+            }
+            Block::BlockQuote(BlockQuote { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. })
+            | Block::JsxComponent(JsxComponent { children, .. }) => {
+                take_footnote_definitions(children, definitions);
+            }
+            Block::List(list) => {
+                for item in &mut list.items {
+                    take_footnote_definitions(&mut item.children, definitions);
+                }
+            }
+            Block::CodeBlock(_)
+            | Block::Heading(_)
+            | Block::Markup(_)
+            | Block::Paragraph(_)
+            | Block::Table(_)
+            | Block::ThematicBreak => {}
+        }
+        index += 1;
     }
 }
 
-fn parse_heading_text(line: &str) -> IResult<&str, usize> {
-    let (heading, level) = terminated(many1_count(tag("#")), multispace1).parse(line)?;
-    Ok((heading, level))
+/* appends `-1`, `-2`, and so on, to any slug from a heading title already used earlier in the
+ * document, or by a custom id, returning the headings in document order
+ */
+fn collect_headings(blocks: &mut [Block]) -> Vec<Heading> {
+    // custom ids give stable anchors, so only slugs from titles take a suffix
+    let mut ids: HashSet<String> = HashSet::new();
+    for block in blocks.iter_mut() {
+        block.visit_blocks_mut(&mut |block| {
+            if let Block::Heading(Heading {
+                id,
+                custom_id: true,
+                ..
+            }) = block
+            {
+                ids.insert(id.clone());
+            }
+        });
+    }
+
+    let mut headings: Vec<Heading> = Vec::new();
+    for block in blocks {
+        block.visit_blocks_mut(&mut |block| {
+            if let Block::Heading(heading) = block {
+                if !heading.custom_id {
+                    let mut id = heading.id.clone();
+                    let mut suffix = 0;
+                    while ids.contains(&id) {
+                        suffix += 1;
+                        id = format!("{}-{suffix}", heading.id);
+                    }
+                    ids.insert(id.clone());
+                    heading.id = id;
+                }
+                headings.push(heading.clone());
+            }
+        });
+    }
+    headings
+}
+
+fn document_heading(heading: &Heading) -> DocumentHeading {
+    let text = heading
+        .content
+        .iter()
+        .map(|inline| match inline {
+            Inline::Code(value) | Inline::Text(value) => value.as_str(),
+            _ => "",
+        })
+        .collect();
+    DocumentHeading {
+        depth: heading.level,
+        slug: heading.id.clone(),
+        text,
+    }
 }
 
-// consumes delimiter
-fn parse_inline_wrap_segment<'a>(
-    line: &'a str,
-    delimiter: &'a str,
-) -> IResult<&'a str, (&'a str, &'a str)> {
-    separated_pair(take_until(delimiter), tag(delimiter), rest).parse(line)
+/* nests each heading under the closest earlier heading with a lower level, linking to the heading
+ * with its own content
+ */
+fn table_of_contents_items(headings: &[Heading]) -> Vec<ListItem> {
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut index = 0;
+    while index < headings.len() {
+        let heading = &headings[index];
+        let end = headings[index + 1..]
+            .iter()
+            .position(|other| other.level <= heading.level)
+            .map_or(headings.len(), |position| index + 1 + position);
+        let href = format!("#{}", heading.id);
+        let mut children = vec![Block::Paragraph(vec![Inline::Link(Link {
+            attributes: format!("href=\"{href}\""),
+            href,
+            has_rel: false,
+            has_target: false,
+            content: heading.content.clone(),
+        })])];
+        let nested_items = table_of_contents_items(&headings[index + 1..end]);
+        if !nested_items.is_empty() {
+            children.push(Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: nested_items,
+            }));
+        }
+        items.push(ListItem {
+            checked: None,
+            children,
+        });
+        index = end;
+    }
+    items
+}
+
+// removes `<TableOfContents />` markers, at any depth, so no empty outline is rendered
+fn remove_table_of_contents_markers(blocks: &mut Vec<Block>) {
+    blocks.retain(|block| {
+        !matches!(
+            block,
+            Block::JsxComponent(JsxComponent {
+                component: JSXComponentType::TableOfContents,
+                ..
+            })
+        )
+    });
+    for block in blocks {
+        match block {
+            Block::BlockQuote(BlockQuote { children, .. })
+            | Block::FootnoteDefinition(FootnoteDefinition { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. })
+            | Block::JsxComponent(JsxComponent { children, .. }) => {
+                remove_table_of_contents_markers(children);
+            }
+            Block::List(list) => {
+                for item in &mut list.items {
+                    remove_table_of_contents_markers(&mut item.children);
+                }
+            }
+            Block::CodeBlock(_)
+            | Block::Heading(_)
+            | Block::Markup(_)
+            | Block::Paragraph(_)
+            | Block::Table(_)
+            | Block::ThematicBreak => {}
+        }
+    }
 }
 
-fn parse_ordered_list_text(line: &str) -> IResult<&str, (usize, &str)> {
-    let (content_text, (indentation, start, _full_stop_tag)) =
-        (many0_count(tag(" ")), digit1, tag(". ")).parse(line)?;
-    Ok((content_text.trim(), (indentation, start)))
+// fills each `<TableOfContents />` marker with the outline, or drops the markers if it is empty
+fn place_table_of_contents(blocks: &mut Vec<Block>, headings: &[Heading]) {
+    let items = table_of_contents_items(headings);
+    if items.is_empty() {
+        remove_table_of_contents_markers(blocks);
+        return;
+    }
+    for block in blocks {
+        block.visit_blocks_mut(&mut |block| {
+            if let Block::JsxComponent(JsxComponent {
+                component: JSXComponentType::TableOfContents,
+                children,
+            }) = block
+            {
+                *children = vec![Block::List(List {
+                    list_type: ListType::Unordered,
+                    start: None,
+                    loose: false,
+                    items: items.clone(),
+                })];
+            }
+        });
+    }
 }
 
-fn parse_unordered_list_text(line: &str) -> IResult<&str, usize> {
-    let (heading, indentation) = terminated(many0_count(tag(" ")), tag("- ")).parse(line)?;
-    Ok((heading, indentation))
+/* replaces `[^label]` shortcut references, which have a footnote definition, numbering footnotes in
+ * order of first reference and recording the label and reference count of each
+ */
+fn resolve_footnote_references(
+    inlines: &mut [Inline],
+    definitions: &HashMap<String, Vec<Block>>,
+    references: &mut Vec<(String, usize)>,
+) {
+    for inline in inlines {
+        if let Inline::LinkReference(LinkReference {
+            label,
+            shortcut: true,
+            ..
+        }) = inline
+        {
+            if definitions.contains_key(label.as_str()) {
+                let index = match references.iter().position(|(value, _)| value == label) {
+                    Some(value) => value,
+                    None => {
+                        references.push((label.clone(), 0));
+                        references.len() - 1
+                    }
+                };
+                references[index].1 += 1;
+                *inline = Inline::FootnoteReference(FootnoteReference {
+                    number: index + 1,
+                    occurrence: references[index].1,
+                    sidenote: None,
+                });
+                continue;
+            }
+        }
+        match inline {
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content)
+            | Inline::Link(Link { content, .. })
+            | Inline::LinkReference(LinkReference { content, .. }) => {
+                resolve_footnote_references(content, definitions, references);
+            }
+            Inline::Code(_)
+            | Inline::FootnoteReference(_)
+            | Inline::Image(_)
+            | Inline::LineBreak
+            | Inline::SoftBreak
+            | Inline::Text(_) => {}
+        }
+    }
 }
 
-fn form_heading_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (value, level) = parse_heading_text(line)?;
-    let parsed_text = form_code_span_html_string(value);
-    let id = slugify_title(value);
+// footnotes without any reference are dropped
+fn collect_footnotes(blocks: &mut Vec<Block>) -> Vec<Footnote> {
+    let mut definitions: HashMap<String, Vec<Block>> = HashMap::new();
+    take_footnote_definitions(blocks, &mut definitions);
+    let mut references: Vec<(String, usize)> = Vec::new();
+    for block in blocks.iter_mut() {
+        block.visit_inlines_mut(&mut |inlines| {
+            resolve_footnote_references(inlines, &definitions, &mut references)
+        });
+    }
+
+    // footnotes may themselves reference later footnotes
+    let mut footnote_children: Vec<Vec<Block>> = Vec::new();
+    while footnote_children.len() < references.len() {
+        let label = &references[footnote_children.len()].0;
+        let mut children = definitions.get(label).cloned().unwrap_or_default();
+        for child in &mut children {
+            child.visit_inlines_mut(&mut |inlines| {
+                resolve_footnote_references(inlines, &definitions, &mut references)
+            });
+        }
+        footnote_children.push(children);
+    }
+    footnote_children
+        .into_iter()
+        .zip(references)
+        .map(|(children, (_, references))| Footnote {
+            children,
+            references,
+        })
+        .collect()
+}
+
+// shortcut references without a definition are left as text
+fn resolve_link_references(
+    inlines: &mut Vec<Inline>,
+    link_definitions: &HashMap<String, LinkDefinition>,
+) {
+    let mut result: Vec<Inline> = Vec::with_capacity(inlines.len());
+    for inline in inlines.drain(..) {
+        match inline {
+            Inline::LinkReference(mut reference) => {
+                resolve_link_references(&mut reference.content, link_definitions);
+                match link_definitions.get(&reference.label) {
+                    Some(LinkDefinition { href, title }) => result.push(Inline::Link(
+                        markdown_link(href, title.as_deref(), reference.content),
+                    )),
+                    None => {
+                        result.push(Inline::Text(String::from("[")));
+                        result.append(&mut reference.content);
+                        result.push(Inline::Text(String::from("]")));
+                    }
+                }
+            }
+            Inline::Emphasis(mut content) => {
+                resolve_link_references(&mut content, link_definitions);
+                result.push(Inline::Emphasis(content));
+            }
+            Inline::Strong(mut content) => {
+                resolve_link_references(&mut content, link_definitions);
+                result.push(Inline::Strong(content));
+            }
+            Inline::Strikethrough(mut content) => {
+                resolve_link_references(&mut content, link_definitions);
+                result.push(Inline::Strikethrough(content));
+            }
+            Inline::Link(mut link) => {
+                resolve_link_references(&mut link.content, link_definitions);
+                result.push(Inline::Link(link));
+            }
+            other => result.push(other),
+        }
+    }
+    *inlines = merge_inline_text(result);
+}
+
+fn form_code_span_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (remaining_line, initial_segment) = take_until("`")(line)?;
+    let (final_segment, code_segment) = parse_code_span(remaining_line)?;
+    let mut result: Vec<Inline> = Vec::new();
+    push_inline_text(&mut result, initial_segment);
+    result.push(Inline::Code(code_segment.to_string()));
+    Ok((final_segment, result))
+}
+
+fn form_backslash_escape_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, escaped) = parse_backslash_escape(line)?;
+    Ok((final_segment, vec![Inline::Text(escaped)]))
+}
+
+fn parse_fenced_code_block_first_line(line: &str) -> IResult<&str, ParsedFencedCodeBlockMeta<'_>> {
+    let (meta, fence) = parse_code_fence(line)?;
+
+    // backticks would be ambiguous with a code span, so may not appear in a backtick fence info string
+    if fence.character == '`' && meta.contains('`') {
+        return Err(Err::Error(LineError::new(meta, ErrorKind::Verify)));
+    }
+    let meta = meta.trim_start();
+    let (remaining_meta, language_option) =
+        opt(alt((terminated(take_until(" "), tag(" ")), alpha1))).parse(meta)?;
+    let (remaining_meta, first_line_number_option) =
+        opt(alt((terminated(digit1, tag(" ")), digit1))).parse(remaining_meta)?;
+    let (remaining_meta, highlight_lines_option) = opt(alt((
+        delimited(peek(tag("{")), is_not(" \t\r\n"), tag(" ")),
+        preceded(peek(tag("{")), is_not(" \t\r\n")),
+    )))
+    .parse(remaining_meta)?;
+    let (remaining_meta, title_option) = opt(alt((
+        delimited(tag("\""), take_until("\" "), tag("\" ")),
+        delimited(tag("\""), take_until("\""), tag("\"")),
+    )))
+    .parse(remaining_meta)?;
+    let (remaining_meta, caption_option) = opt(alt((
+        delimited(tag("["), take_until("] "), tag("] ")),
+        delimited(tag("["), take_until("]"), tag("]")),
+    )))
+    .parse(remaining_meta)?;
+    let (_, collapse_option_tag) = opt(tag("<>")).parse(remaining_meta)?;
+    let collapse_option = match collapse_option_tag {
+        Some("<>") => Some(true),
+        _ => Some(false),
+    };
     Ok((
         "",
         (
-            format!(
-                "<h{level} id=\"{id}\"><Heading client:visible id=\"{id}\" text=\"{}\"/></h{level}>",
-                format_heading_widows(parsed_text.trim_end())
-            ),
-            LineType::Heading,
-            level,
+            language_option,
+            first_line_number_option,
+            highlight_lines_option,
+            title_option,
+            caption_option,
+            collapse_option,
         ),
     ))
 }
 
-fn form_html_block_level_comment_first_line(
-    line: &str,
-) -> IResult<&str, (String, LineType, usize)> {
-    parse_html_block_level_comment_first_line(line)?;
+fn parse_block_quote_marker(line: &str) -> IResult<&str, &str> {
+    preceded(
+        many_m_n(0, 3, tag(" ")),
+        terminated(tag(">"), opt(tag(" "))),
+    )
+    .parse(line)
+}
+
+// strips up to `max_depth` blockquote markers, returning the number stripped and the line content
+fn strip_block_quote_markers(line: &str, max_depth: usize) -> (usize, &str) {
+    let mut depth = 0;
+    let mut content = line;
+    while depth < max_depth {
+        match parse_block_quote_marker(content) {
+            Ok((remaining_content, _)) => {
+                content = remaining_content;
+                depth += 1;
+            }
+            Err(_) => break,
+        }
+    }
+    (depth, content)
+}
+
+// first line of a GitHub-style callout, for example `[!NOTE]`
+fn parse_callout_first_line(line: &str) -> IResult<&str, CalloutType> {
+    terminated(
+        delimited(
+            tag("[!"),
+            alt((
+                value(CalloutType::Caution, tag_no_case("CAUTION")),
+                value(CalloutType::Important, tag_no_case("IMPORTANT")),
+                value(CalloutType::Note, tag_no_case("NOTE")),
+                value(CalloutType::Tip, tag_no_case("TIP")),
+                value(CalloutType::Warning, tag_no_case("WARNING")),
+            )),
+            tag("]"),
+        ),
+        pair(multispace0, eof),
+    )
+    .parse(line)
+}
+
+// a run of at least three backticks or tildes, indented by at most three spaces
+fn parse_code_fence(line: &str) -> IResult<&str, CodeFence> {
+    let (remaining_line, (indentation, run)) = (
+        many_m_n(0, 3, tag(" ")),
+        alt((take_while1(|c| c == '`'), take_while1(|c| c == '~'))),
+    )
+        .parse(line)?;
+    if run.len() < 3 {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Count)));
+    }
+    Ok((
+        remaining_line,
+        CodeFence {
+            character: run.chars().next().unwrap_or('`'),
+            length: run.len(),
+            indentation: indentation.len(),
+        },
+    ))
+}
+
+/* three or more `-`, `*` or `_` characters, all the same, optionally separated by spaces or tabs
+ * and indented by at most three spaces
+ */
+fn parse_thematic_break(line: &str) -> IResult<&str, char> {
+    let (remaining_line, _) = many_m_n(0, 3, tag(" ")).parse(line)?;
+    let (_, character) = one_of("-*_")(remaining_line)?;
+    let (remaining_line, run) = terminated(
+        take_while1(|c| c == character || c == ' ' || c == '\t'),
+        eof,
+    )
+    .parse(remaining_line)?;
+    if run.chars().filter(|c| *c == character).count() < 3 {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Count)));
+    }
+    Ok((remaining_line, character))
+}
+
+fn form_thematic_break_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (remaining_line, _) = parse_thematic_break(line)?;
+    Ok((
+        remaining_line,
+        (Block::ThematicBreak, LineType::ThematicBreak, 0),
+    ))
+}
+
+// closing fence uses the opening fence character and is at least as long, with nothing following it
+fn parse_fenced_code_block_last_line(line: &str, fence: CodeFence) -> IResult<&str, CodeFence> {
+    let (remaining_line, closing_fence) =
+        terminated(parse_code_fence, pair(space0, eof)).parse(line)?;
+    if closing_fence.character != fence.character || closing_fence.length < fence.length {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Verify)));
+    }
+    Ok((remaining_line, closing_fence))
+}
+
+fn parse_html_block_level_comment_first_line(line: &str) -> IResult<&str, &str> {
+    tag("<!--")(line)
+}
+
+fn parse_html_block_level_comment_last_line(line: &str) -> IResult<&str, &str> {
+    terminated(take_until("-->"), tag("-->")).parse(line)
+}
+
+// delimiter row cell, with optional colons marking the alignment, and any number of dashes
+fn parse_table_column_alignment(cell: &str) -> IResult<&str, Option<TableAlign>> {
+    let (remaining_cell, (left, _, right)) = (
+        opt(tag(":")),
+        take_while1(|character| character == '-'),
+        opt(tag(":")),
+    )
+        .parse(cell.trim())?;
+    let (remaining_cell, _) = eof(remaining_cell)?;
+    let alignment = match (left, right) {
+        (Some(_), Some(_)) => Some(TableAlign::Centre),
+        (Some(_), None) => Some(TableAlign::Left),
+        (None, Some(_)) => Some(TableAlign::Right),
+        (None, None) => None,
+    };
+    Ok((remaining_cell, alignment))
+}
+
+// cell content up to the next pipe, skipping escaped pipes (`\|`)
+fn parse_table_cell(line: &str) -> IResult<&str, &str> {
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '|' if !escaped => return Ok((line[index + 1..].trim_start(), &line[..index])),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)))
+}
+
+/* parses row separating header and body containing alignment markers, which needs at least one
+ * pipe, so it is not mistaken for a thematic break
+ */
+fn parse_table_header_row(line: &str) -> IResult<&str, Vec<Option<TableAlign>>> {
+    if !line.contains('|') {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Char)));
+    }
+    let (remaining_line, cells) = parse_table_line(line)?;
+    let alignments = cells
+        .into_iter()
+        .map(|cell| parse_table_column_alignment(cell).map(|(_, alignment)| alignment))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((remaining_line, alignments))
+}
+
+// splits a row into cells, with the leading and trailing pipes optional
+fn parse_table_line(line: &str) -> IResult<&str, Vec<&str>> {
+    let line = line.trim();
+    let mut remaining_line = line.strip_prefix('|').unwrap_or(line).trim_start();
+    let mut cells: Vec<&str> = Vec::new();
+    while let Ok((final_segment, cell)) = parse_table_cell(remaining_line) {
+        cells.push(cell);
+        remaining_line = final_segment;
+    }
+    if !remaining_line.is_empty() || cells.is_empty() {
+        cells.push(remaining_line);
+    }
+    Ok(("", cells))
+}
+
+fn form_html_block_element_first_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (remaining_line, (tag_name, _tag_attributes, _tag_type)) = parse_opening_html_tag(line)?;
+    let markup = Block::Markup(String::from(line));
+    match tag_name {
+        "dl" => Ok(("", (markup, LineType::HTMLDescriptionListOpen, 0))),
+        "div" => Ok(("", (markup, LineType::HTMLDivBlockOpen, 0))),
+        "figure" => Ok(("", (markup, LineType::HTMLFigureBlockOpen, 0))),
+        _ => Err(LineError::failure(
+            &line[..line.len() - remaining_line.len()],
+            ParseErrorKind::UnrecognisedBlockElement(tag_name.to_string()),
+        )),
+    }
+}
+
+fn form_html_block_element_last_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_remaining_line, (tag_name, _tag_attributes, _tag_type)) = parse_closing_html_tag(line)?;
+    let markup = Block::Markup(String::from(line));
+    match tag_name {
+        "dl" => Ok(("", (markup, LineType::HTMLDescriptionList, 0))),
+        "div" => Ok(("", (markup, LineType::HTMLDivBlock, 0))),
+        "figure" => Ok(("", (markup, LineType::HTMLFigureBlock, 0))),
+        _ => Err(Err::Error(LineError::new(line, ErrorKind::Tag))),
+    }
+}
+
+fn form_fenced_code_block_first_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (
+        _,
+        (
+            language_option,
+            first_line_number_option,
+            highlight_line_numbers_option,
+            title_option,
+            caption_option,
+            collapse_option,
+        ),
+    ) = parse_fenced_code_block_first_line(line)?;
+
+    let code_block = CodeBlock {
+        language: language_option.map(String::from),
+        first_line_number: first_line_number_option.map(String::from),
+        highlight_line_numbers: highlight_line_numbers_option.map(String::from),
+        title: title_option.map(String::from),
+        caption: caption_option.map(String::from),
+        collapse: collapse_option == Some(true),
+        lines: Vec::new(),
+    };
     Ok((
         "",
         (
-            line.trim_end().to_string(),
-            LineType::HTMLBlockLevelCommentOpen,
+            Block::CodeBlock(code_block),
+            LineType::FencedCodeBlockOpen,
             0,
         ),
     ))
 }
 
-fn form_html_block_level_comment_last_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    match parse_html_block_level_comment_last_line(line) {
-        Ok((after_comment, end_of_comment)) => {
-            let (_, after_comment) = parse_inline_wrap_text(after_comment)?;
-            let markup = format!("{end_of_comment}-->{}", after_comment.trim_end());
-            Ok(("", (markup, LineType::HTMLBlockLevelComment, 0)))
+/* replaces escaped pipes in code spans, once the cell content is parsed, so error spans still point
+ * into the line; other escaped pipes are already backslash escapes
+ */
+fn unescape_table_cell_pipes(inlines: &mut [Inline]) {
+    for inline in inlines {
+        match inline {
+            Inline::Code(value) => {
+                if value.contains("\\|") {
+                    *value = value.replace("\\|", "|");
+                }
+            }
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content) => {
+                unescape_table_cell_pipes(content);
+            }
+            Inline::Link(Link { content, .. })
+            | Inline::LinkReference(LinkReference { content, .. }) => {
+                unescape_table_cell_pipes(content);
+            }
+            Inline::FootnoteReference(_)
+            | Inline::Image(_)
+            | Inline::LineBreak
+            | Inline::SoftBreak
+            | Inline::Text(_) => {}
         }
-        Err(_) => Ok((
+    }
+}
+
+fn form_table_row(cells: Vec<&str>) -> Result<TableRow, Err<LineError<&str>>> {
+    let cells = cells
+        .into_iter()
+        .map(|cell| {
+            let (_, mut content) = parse_inline_wrap_text(cell.trim())?;
+            unescape_table_cell_pipes(&mut content);
+            Ok(content)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TableRow { cells })
+}
+
+fn form_table_body_row(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_, cells) = parse_table_line(line)?;
+    let row = form_table_row(cells)?;
+    let table = Table {
+        body: vec![row],
+        ..Default::default()
+    };
+    Ok(("", (Block::Table(table), LineType::HTMLTableBodyOpen, 0)))
+}
+
+/* table header row, which only starts a table when the next line is a delimiter row with the same
+ * number of cells
+ */
+fn form_table_first_line<'a>(
+    line: &'a str,
+    next_line: Option<&str>,
+) -> IResult<&'a str, (Block, LineType, usize)> {
+    let columns = match next_line.map(parse_table_header_row) {
+        Some(Ok((_, alignments))) => alignments.len(),
+        _ => return Err(Err::Error(LineError::new(line, ErrorKind::Verify))),
+    };
+    let (_, cells) = parse_table_line(line)?;
+    if cells.len() != columns {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Verify)));
+    }
+    form_table_head_row(line)
+}
+
+// regular row in table head
+fn form_table_head_row(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_, cells) = parse_table_line(line)?;
+    let row = form_table_row(cells)?;
+    let table = Table {
+        head: vec![row],
+        ..Default::default()
+    };
+    Ok(("", (Block::Table(table), LineType::HTMLTableHeadOpen, 0)))
+}
+
+// special row between head and body with alignment markers
+fn form_table_header_row(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_, alignments) = parse_table_header_row(line)?;
+    let table = Table {
+        alignments,
+        ..Default::default()
+    };
+    Ok(("", (Block::Table(table), LineType::HTMLTableBodyOpen, 0)))
+}
+
+// a blank line or the start of another block ends the table body, and any other line is a row
+fn form_table_body_last_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    if line.trim().is_empty() {
+        return Ok((
             "",
-            (
-                line.trim_end().to_string(),
-                LineType::HTMLBlockLevelCommentOpen,
-                0,
+            (Block::Table(Table::default()), LineType::HTMLTableBody, 0),
+        ));
+    }
+    match parse_mdx_line(line, None) {
+        Ok(Some((_, LineType::Paragraph, _))) => form_table_body_row(line),
+        Ok(_) => Err(Err::Error(LineError::new(line, ErrorKind::Verify))),
+        Err(error) => Err(Err::Failure(error)),
+    }
+}
+
+// optimistically try to end the head section or alternatively add additional head line
+fn form_table_head_last_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    alt((form_table_header_row, form_table_head_row)).parse(line)
+}
+
+fn form_fenced_code_block_last_line(
+    line: &str,
+    fence: CodeFence,
+) -> IResult<&str, (String, LineType, usize)> {
+    parse_fenced_code_block_last_line(line, fence)?;
+    Ok(("", (String::from(line), LineType::FencedCodeBlock, 0)))
+}
+
+fn form_strikethrough_line(line: &str) -> IResult<&str, Vec<Inline>> {
+    let (final_segment, deleted_segment) =
+        delimited(tag("~~"), take_until("~~"), tag("~~")).parse(line)?;
+    if deleted_segment.trim().is_empty() {
+        return Err(Err::Error(LineError::new(line, ErrorKind::TakeUntil)));
+    }
+    let (_, content) = parse_inline_wrap_text(deleted_segment)?;
+    Ok((final_segment, vec![Inline::Strikethrough(content)]))
+}
+
+/* splits the line into inline markup and emphasis delimiter runs, which are only matched up once
+ * the whole line is read
+ */
+fn parse_inline_wrap_text(line: &str) -> IResult<&str, Vec<Inline>> {
+    fn is_wrap_tag(c: char) -> bool {
+        c == '`'
+            || c == '\\'
+            || c == '*'
+            || c == '_'
+            || c == '<'
+            || c == '['
+            || c == '!'
+            || c == '~'
+    }
+
+    fn push_text_token(tokens: &mut Vec<InlineToken>, text: &str) {
+        if !text.is_empty() {
+            tokens.push(InlineToken::Inline(Inline::Text(text.to_string())));
+        }
+    }
+
+    let mut tokens: Vec<InlineToken> = Vec::new();
+    let mut text_start = 0;
+    let mut position = 0;
+    loop {
+        let remaining_line = &line[position..];
+        let next_tag = match (
+            remaining_line.find(is_wrap_tag),
+            find_autolink(remaining_line),
+        ) {
+            (Some(wrap_tag), Some(autolink)) => Some(wrap_tag.min(autolink)),
+            (wrap_tag, autolink) => wrap_tag.or(autolink),
+        };
+        let Some(next_tag) = next_tag else {
+            break;
+        };
+        let tag_position = position + next_tag;
+        let line_from_tag = &line[tag_position..];
+        let tag_character = line_from_tag.chars().next().unwrap_or_default();
+
+        if tag_character == '*' || tag_character == '_' {
+            let length = line_from_tag
+                .chars()
+                .take_while(|c| *c == tag_character)
+                .count();
+            push_text_token(&mut tokens, &line[text_start..tag_position]);
+            tokens.push(InlineToken::Delimiter(delimiter_run(
+                tag_character,
+                length,
+                line[..tag_position].chars().next_back(),
+                line_from_tag[length..].chars().next(),
+            )));
+            position = tag_position + length;
+            text_start = position;
+            continue;
+        }
+
+        let parsed_result = match tag_character {
+            '`' => form_code_span_line(line_from_tag),
+            '\\' => form_backslash_escape_line(line_from_tag),
+            '<' => alt((form_html_anchor_element_line, form_autolink_line)).parse(line_from_tag),
+            '[' => alt((form_markdown_link_line, form_reference_link_line)).parse(line_from_tag),
+            '!' => form_markdown_image_line(line_from_tag),
+            '~' => form_strikethrough_line(line_from_tag),
+            _ => form_autolink_line(line_from_tag),
+        };
+        match parsed_result {
+            Ok((final_segment, inlines)) => {
+                push_text_token(&mut tokens, &line[text_start..tag_position]);
+                tokens.extend(inlines.into_iter().map(InlineToken::Inline));
+                position = line.len() - final_segment.len();
+                text_start = position;
+            }
+            Err(Err::Failure(error)) => return Err(Err::Failure(error)),
+
+            // an unmatched backtick run is text, so cannot open a shorter code span
+            Err(_) if tag_character == '`' => {
+                position = tag_position + backtick_run_length(line_from_tag);
+            }
+
+            // not markup, so keep the character as text and carry on after it
+            Err(_) => position = tag_position + tag_character.len_utf8(),
+        }
+    }
+    push_text_token(&mut tokens, &line[text_start..]);
+    Ok(("", process_emphasis(tokens)))
+}
+
+// joins neighbouring text nodes into one
+fn merge_inline_text(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut result: Vec<Inline> = Vec::with_capacity(inlines.len());
+    for inline in inlines {
+        match (result.last_mut(), inline) {
+            (Some(Inline::Text(previous)), Inline::Text(text)) => previous.push_str(&text),
+            (_, inline) => result.push(inline),
+        }
+    }
+    result
+}
+
+fn parse_heading_text(line: &str) -> IResult<&str, usize> {
+    let (heading, level) = terminated(many1_count(tag("#")), multispace1).parse(line)?;
+    Ok((heading, level))
+}
+
+// an optional closing run of `#` characters, after a space or tab, is not part of the heading
+fn strip_heading_closing_sequence(heading: &str) -> &str {
+    let heading = heading.trim_end();
+    let without_closing_sequence = heading.trim_end_matches('#');
+    if without_closing_sequence.is_empty() {
+        without_closing_sequence
+    } else if without_closing_sequence.ends_with([' ', '\t']) {
+        without_closing_sequence.trim_end()
+    } else {
+        heading
+    }
+}
+
+/* run of `=` (level one) or `-` (level two) characters, under paragraph text, with no spaces between
+ * them
+ */
+fn parse_setext_heading_underline(line: &str) -> IResult<&str, usize> {
+    let (remaining_line, (_, run, _, _)) = (
+        many_m_n(0, 3, tag(" ")),
+        alt((take_while1(|c| c == '='), take_while1(|c| c == '-'))),
+        space0,
+        eof,
+    )
+        .parse(line)?;
+    let level = if run.starts_with('=') { 1 } else { 2 };
+    Ok((remaining_line, level))
+}
+
+fn parse_ordered_list_text(line: &str) -> IResult<&str, (usize, &str)> {
+    let (content_text, (indentation, start, _delimiter_tag)) = (
+        many0_count(tag(" ")),
+        take_while_m_n(1, 9, |c: char| c.is_ascii_digit()),
+        alt((tag(". "), tag(") "))),
+    )
+        .parse(line)?;
+    Ok((content_text.trim(), (indentation, start)))
+}
+
+/* list marker, with its indentation and following spaces, returning the bullet or ordered list
+ * delimiter; item content starts after the marker
+ */
+fn parse_list_item_marker(line: &str) -> IResult<&str, char> {
+    delimited(
+        space0,
+        alt((
+            preceded(
+                take_while_m_n(1, 9, |c: char| c.is_ascii_digit()),
+                one_of(".)"),
             ),
+            one_of("-*+"),
         )),
+        space1,
+    )
+    .parse(line)
+}
+
+// code indented by four spaces, or a tab, returning the code without the indentation
+fn parse_indented_code_line(line: &str) -> IResult<&str, &str> {
+    let (code, _) = alt((tag("    "), tag("\t"))).parse(line)?;
+    if code.trim().is_empty() {
+        return Err(Err::Error(LineError::new(line, ErrorKind::Verify)));
     }
+    Ok(("", code))
 }
 
-fn form_ordered_list_first_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (list_text, (indentation, start)) = parse_ordered_list_text(line)?;
-    let (_, parsed_list_text) = parse_inline_wrap_text(list_text)?;
-    let markup = match start {
-        "1" => format!("<ol>\n  <li>{parsed_list_text}"),
-        _ => format!("<ol start=\"{start}\">\n  <li>{parsed_list_text}"),
+fn parse_unordered_list_text(line: &str) -> IResult<&str, usize> {
+    let (heading, indentation) = terminated(
+        many0_count(tag(" ")),
+        alt((tag("- "), tag("* "), tag("+ "))),
+    )
+    .parse(line)?;
+    Ok((heading, indentation))
+}
+
+/* `{#custom-id .class}` attribute block, ending a heading, returning the id, if any, and classes in
+ * source order
+ */
+fn parse_heading_attributes(line: &str) -> IResult<&str, (Option<&str>, Vec<&str>)> {
+    let identifier = || take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == '_');
+    let (remaining_line, attributes) = delimited(
+        pair(tag("{"), space0),
+        separated_list1(
+            space1,
+            alt((
+                map(preceded(tag("#"), identifier()), |id| (Some(id), None)),
+                map(preceded(tag("."), identifier()), |class| {
+                    (None, Some(class))
+                }),
+            )),
+        ),
+        (space0, tag("}"), space0, eof),
+    )
+    .parse(line)?;
+    let id = attributes.iter().rev().find_map(|(id, _)| *id);
+    let classes = attributes.iter().filter_map(|(_, class)| *class).collect();
+    Ok((remaining_line, (id, classes)))
+}
+
+// an attribute block must follow the title text, after a space or tab
+fn split_heading_attributes(title: &str) -> (&str, Option<&str>, Vec<&str>) {
+    match title.rfind('{') {
+        Some(index) if index > 0 && title[..index].ends_with([' ', '\t']) => {
+            match parse_heading_attributes(&title[index..]) {
+                Ok((_, (id, classes))) => (title[..index].trim_end(), id, classes),
+                Err(_) => (title, None, Vec::new()),
+            }
+        }
+        _ => (title, None, Vec::new()),
+    }
+}
+
+// ids are made unique, across the document, once it is parsed
+fn form_heading(
+    (title, id, classes): (&str, Option<&str>, Vec<&str>),
+    level: usize,
+) -> (Block, LineType, usize) {
+    let heading = Heading {
+        level,
+        id: id.map_or_else(|| slugify_title(title), String::from),
+        custom_id: id.is_some(),
+        classes: classes.into_iter().map(String::from).collect(),
+        content: parse_heading_inline_text(title),
     };
-    Ok(("", (markup, LineType::OrderedListItemOpen, indentation)))
+    (Block::Heading(heading), LineType::Heading, level)
 }
 
-fn form_ordered_list_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (list_text, (indentation, _start)) = parse_ordered_list_text(line)?;
-    let (_, parsed_list_text) = parse_inline_wrap_text(list_text)?;
+fn form_heading_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (value, level) = parse_heading_text(line)?;
+
+    // the attribute block may follow the closing sequence, or come before it
+    let (title, id, classes) = split_heading_attributes(strip_heading_closing_sequence(value));
+    Ok((
+        "",
+        form_heading((strip_heading_closing_sequence(title), id, classes), level),
+    ))
+}
+
+// the lines of paragraph text over the underline form the title
+fn form_setext_heading(lines: &[(usize, &str)], level: usize) -> (Block, LineType, usize) {
+    let title = lines
+        .iter()
+        .map(|(_, line)| line.trim())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    form_heading(split_heading_attributes(&title), level)
+}
+
+fn form_html_block_level_comment_first_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    parse_html_block_level_comment_first_line(line)?;
     Ok((
         "",
         (
-            format!("  <li>{parsed_list_text}"),
-            LineType::OrderedListItemOpen,
-            indentation,
+            Block::Markup(line.trim_end().to_string()),
+            LineType::HTMLBlockLevelCommentOpen,
+            0,
         ),
     ))
 }
 
-fn form_unordered_list_line(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (list_text, indentation) = parse_unordered_list_text(line)?;
-    let (_, parsed_list_text) = parse_inline_wrap_text(list_text)?;
+// like CommonMark, text following the end of the comment is passed through with it
+fn form_html_block_level_comment_last_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let line_type = match parse_html_block_level_comment_last_line(line) {
+        Ok(_) => LineType::HTMLBlockLevelComment,
+        Err(_) => LineType::HTMLBlockLevelCommentOpen,
+    };
     Ok((
         "",
+        (Block::Markup(line.trim_end().to_string()), line_type, 0),
+    ))
+}
+
+// GFM task list item marker, `[ ]` or `[x]`, at the start of the item text
+fn parse_task_list_marker(line: &str) -> IResult<&str, bool> {
+    terminated(
+        alt((value(false, tag("[ ]")), value(true, tag_no_case("[x]")))),
+        multispace1,
+    )
+    .parse(line)
+}
+
+/* list item with any task list marker split off, returning the item text, which starts a paragraph
+ * following lines may continue
+ */
+fn form_list_item(list_text: &str) -> IResult<&str, ListItem> {
+    let (list_text, checked) = opt(parse_task_list_marker).parse(list_text)?;
+    Ok((
+        list_text,
+        ListItem {
+            checked,
+            children: Vec::new(),
+        },
+    ))
+}
+
+// each list line parses to a single item list, which is merged into any open list
+fn form_ordered_list_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (list_text, (indentation, start)) = parse_ordered_list_text(line)?;
+    let (item_text, item) = form_list_item(list_text)?;
+    let list = List {
+        list_type: ListType::Ordered,
+        start: Some(start.to_string()),
+        loose: false,
+        items: vec![item],
+    };
+    Ok((
+        item_text,
         (
-            format!("<li>\n  {parsed_list_text}\n</li>"),
-            LineType::UnorderedListItem,
+            Block::List(list),
+            LineType::OrderedListItemOpen,
             indentation,
         ),
     ))
 }
 
-fn form_inline_wrap_text(line: &str) -> IResult<&str, (String, LineType, usize)> {
-    let (_, parsed_line) = parse_inline_wrap_text(line)?;
-    let parsed_line = if let Ok((_, value)) = format_inline_wrap_text_number_range(&parsed_line) {
-        value
-    } else {
-        parsed_line
+fn form_unordered_list_line(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (list_text, indentation) = parse_unordered_list_text(line)?;
+    let (item_text, item) = form_list_item(list_text)?;
+    let list = List {
+        list_type: ListType::Unordered,
+        start: None,
+        loose: false,
+        items: vec![item],
     };
-    if parsed_line.is_empty() {
-        Ok(("", (String::new(), LineType::Paragraph, 0)))
-    } else {
-        Ok((
-            "",
-            (format!("<p>{parsed_line}</p>"), LineType::Paragraph, 0),
-        ))
+    Ok((
+        item_text,
+        (Block::List(list), LineType::UnorderedListItem, indentation),
+    ))
+}
+
+/* splits text at the line endings of a multi-line paragraph, into soft or, where the line ended in
+ * a backslash, hard breaks; code spans are kept on one line
+ */
+fn form_paragraph_line_breaks(inlines: Vec<Inline>) -> Vec<Inline> {
+    let mut result: Vec<Inline> = Vec::with_capacity(inlines.len());
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => {
+                let mut segments = text.split('\n');
+                if let Some(first_segment) = segments.next() {
+                    push_inline_text(&mut result, first_segment);
+                }
+                for segment in segments {
+                    match result.last_mut() {
+                        Some(Inline::Text(previous)) if previous.ends_with('\\') => {
+                            previous.pop();
+                            if previous.is_empty() {
+                                result.pop();
+                            }
+                            result.push(Inline::LineBreak);
+                        }
+                        _ => result.push(Inline::SoftBreak),
+                    }
+                    push_inline_text(&mut result, segment);
+                }
+            }
+            Inline::Code(code) => result.push(Inline::Code(code.replace('\n', " "))),
+            Inline::Emphasis(content) => {
+                result.push(Inline::Emphasis(form_paragraph_line_breaks(content)));
+            }
+            Inline::Strong(content) => {
+                result.push(Inline::Strong(form_paragraph_line_breaks(content)));
+            }
+            Inline::Link(mut link) => {
+                link.content = form_paragraph_line_breaks(link.content);
+                result.push(Inline::Link(link));
+            }
+            Inline::LinkReference(mut reference) => {
+                reference.content = form_paragraph_line_breaks(reference.content);
+                result.push(Inline::LinkReference(reference));
+            }
+            other => result.push(other),
+        }
     }
+    result
 }
 
-fn form_astro_frontmatter(
-    components: &HashSet<JSXComponentType>,
-    prepared_markup: &[String],
-    slug: &str,
-) -> Vec<String> {
-    let mut result: Vec<String> = Vec::new();
-    let mut define_slug = false;
-    let mut image_data_imports: Vec<String> = Vec::new();
+/* parses the lines of a paragraph as a single run of text, so inline markup can span lines;
+ * errors are reported against the source line they fall on
+ */
+fn parse_paragraph<'a>(
+    lines: &[(usize, &'a str)],
+    file: &str,
+    source: &str,
+) -> Result<Vec<Inline>, ParseError> {
+    let mut text = String::new();
+    let mut segments: Vec<(usize, usize, &'a str)> = Vec::with_capacity(lines.len());
+    for (index, (line_index, line_content)) in lines.iter().enumerate() {
+        let mut segment = if index == 0 {
+            *line_content
+        } else {
+            line_content.trim_start()
+        };
+        if index + 1 < lines.len() {
+            let trimmed_segment = segment.trim_end();
+            let hard_break = trimmed_segment.ends_with('\\') || segment.ends_with("  ");
+            segment = trimmed_segment
+                .strip_suffix('\\')
+                .unwrap_or(trimmed_segment);
+            segments.push((text.len(), *line_index, segment));
+            text.push_str(segment);
+            text.push_str(if hard_break { "\\\n" } else { "\n" });
+        } else {
+            segments.push((text.len(), *line_index, segment));
+            text.push_str(segment);
+        }
+    }
 
-    result.push(String::from("---"));
-    if components.contains(&JSXComponentType::CodeFragment) {
-        result.push(String::from(
-            "import CodeFragment from '~components/CodeFragment.svelte';",
-        ));
+    match parse_inline_wrap_text(&text) {
+        Ok((_, content)) => Ok(form_paragraph_line_breaks(content)),
+        Err(Err::Error(error) | Err::Failure(error)) => {
+            let offset = text.offset(error.input);
+            let (segment_start, line_index, segment) = segments
+                .iter()
+                .rev()
+                .find(|(segment_start, _, _)| *segment_start <= offset)
+                .copied()
+                .unwrap_or(segments[0]);
+            let start = (offset - segment_start).min(segment.len());
+            let end = (start + error.input.len()).min(segment.len());
+            let line = source.lines().nth(line_index).unwrap_or_default();
+            let line_error = LineError {
+                input: &segment[start..end],
+                kind: error.kind,
+            };
+            Err(ParseError::from_line_error(
+                &line_error,
+                file,
+                source,
+                line,
+                line_index + 1,
+            ))
+        }
+        Err(Err::Incomplete(_)) => Ok(Vec::new()),
     }
-    result.push(String::from(
-        "import Heading from '~components/Heading.svelte';",
-    ));
-    if components.contains(&JSXComponentType::HowTo) {
-        define_slug = true;
-        result.push(String::from(
-            "import HowTo from '~components/HowTo/index.svelte';
-import HowToSection from '~components/HowTo/HowToSection.svelte';
-import HowToStep from '~components/HowTo/HowToStep.svelte';
-import HowToDirection from '~components/HowTo/HowToDirection.svelte';",
-        ));
+}
+
+fn form_inline_wrap_text(line: &str) -> IResult<&str, (Block, LineType, usize)> {
+    let (_, content) = parse_inline_wrap_text(line)?;
+    Ok(("", (Block::Paragraph(content), LineType::Paragraph, 0)))
+}
+
+fn parse_open_html_block<'a>(
+    line: &'a str,
+    open_html_block_elements: Option<&HTMLBlockElementType>,
+) -> Result<Option<(Block, LineType, usize)>, LineError<&'a str>> {
+    let markup = || Block::Markup(line.to_string());
+    let result = match open_html_block_elements {
+        Some(HTMLBlockElementType::Div) => match form_html_block_element_last_line(line) {
+            Ok((_, value)) => Some(value),
+            Err(_) => Some((markup(), LineType::HTMLDivBlockOpen, 0)),
+        },
+        Some(HTMLBlockElementType::Figure) => match form_html_block_element_last_line(line) {
+            Ok((_, value)) => Some(value),
+            Err(_) => Some((markup(), LineType::HTMLFigureBlockOpen, 0)),
+        },
+        Some(HTMLBlockElementType::DescriptionList) => {
+            match form_html_block_element_last_line(line) {
+                Ok((_, value)) => Some(value),
+                Err(_) => Some((markup(), LineType::HTMLDescriptionListOpen, 0)),
+            }
+        }
+        Some(HTMLBlockElementType::TableBody) => match form_table_body_last_line(line) {
+            Ok((_, value)) => Some(value),
+            Err(Err::Failure(error)) => return Err(error),
+            Err(_) => None,
+        },
+        Some(HTMLBlockElementType::TableHead) => match form_table_head_last_line(line) {
+            Ok((_, value)) => Some(value),
+            Err(_) => None,
+        },
+        Some(HTMLBlockElementType::Comment) => {
+            match form_html_block_level_comment_last_line(line) {
+                Ok((_, value)) => Some(value),
+                Err(_) => None,
+            }
+        }
+        // blockquote markers are stripped before the line is parsed
+        Some(HTMLBlockElementType::BlockQuote) | None => None,
+    };
+    Ok(result)
+}
+
+fn parse_mdx_lines<'a>(
+    line: &'a str,
+    next_line: Option<&str>,
+    open_html_block_elements: Option<&HTMLBlockElementType>,
+    open_jsx_component_register: &mut JSXComponentRegister,
+) -> Result<Option<(Block, LineType, usize)>, LineError<&'a str>> {
+    // code block content is literal, so must not close any enclosing list or HTML block
+    if open_jsx_component_register.code_fence().is_some() {
+        return parse_open_jsx_block(line, open_jsx_component_register);
     }
-    if components.contains(&JSXComponentType::GatsbyNotMaintained) {
-        result.push(String::from(
-            "import GatsbyNotMaintained from '~components/BlogPost/GatsbyNotMaintained.svelte';",
-        ));
+    match parse_open_html_block(line, open_html_block_elements)? {
+        Some((_parsed_line, LineType::HTMLDivBlockOpen, _indentation)) => {
+            parse_mdx_line(line, next_line)
+        }
+        Some(value) => Ok(Some(value)),
+        None => match parse_open_jsx_block(line, open_jsx_component_register)? {
+            Some(value) => Ok(Some(value)),
+            None => parse_mdx_line(line, next_line),
+        },
+    }
+}
+
+// JSX component lines pass through unchanged
+fn markup_line((markup, line_type, level): (String, LineType, usize)) -> (Block, LineType, usize) {
+    (Block::Markup(markup), line_type, level)
+}
+
+// the next line is only needed to recognise the header row of a table
+fn parse_mdx_line<'a>(
+    line: &'a str,
+    next_line: Option<&str>,
+) -> Result<Option<(Block, LineType, usize)>, LineError<&'a str>> {
+    if line.trim().is_empty() {
+        return Ok(None);
     }
-    if components.contains(&JSXComponentType::Image)
-        || components.contains(&JSXComponentType::Video)
-        || components.contains(&JSXComponentType::Tweet)
+    match alt((
+        map(form_code_fragment_component_first_line, markup_line),
+        form_fenced_code_block_first_line,
+        // form_how_to_component_first_line,
+        form_html_block_level_comment_first_line,
+        |line| form_table_first_line(line, next_line),
+        map(form_image_component, markup_line),
+        map(form_poll_component_first_line, markup_line),
+        map(form_questions_component, markup_line),
+        map(form_table_of_contents_component, markup_line),
+        map(form_tweet_component, markup_line),
+        map(form_gatsby_not_maintained_component, markup_line),
+        map(form_video_component_first_line, markup_line),
+        form_html_block_element_first_line,
+        form_heading_line,
+        // `* * *` and `- - -` are breaks rather than list items
+        form_thematic_break_line,
+        form_ordered_list_line,
+        form_unordered_list_line,
+        form_footnote_definition_line,
+        form_link_reference_definition_line,
+        form_inline_wrap_text,
+    ))
+    .parse(line)
     {
-        result.push(String::from("import { getEntry } from 'astro:content';"));
+        Ok((_, value)) => Ok(Some(value)),
+        Err(Err::Failure(error)) => Err(error),
+        Err(_) => Ok(None),
     }
-    if components.contains(&JSXComponentType::Image) {
-        define_slug = true;
-        image_data_imports.push(String::from("images"));
-        result.push(String::from(
-            "import Image from '~components/BlogPost/Image.svelte';",
-        ));
+}
+
+pub fn slug_from_input_file_path<P: AsRef<Path>>(path: &P) -> Result<&str, ParseError> {
+    let path = path.as_ref();
+    let slug = match path.file_stem().and_then(|value| value.to_str()) {
+        Some("index") => path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|value| value.to_str()),
+        other => other,
+    };
+    slug.ok_or_else(|| ParseError::Slug {
+        path: path.display().to_string(),
+    })
+}
+
+/// How local image files, included with Markdown image syntax, are output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ImageOutput {
+    /// `Image` component, with pictures from the post's `post-images` content collection entry,
+    /// numbered in the order they first appear
+    #[default]
+    PostImages,
+
+    /// Astro `Image` component, with each file imported from `astro:assets`
+    AstroAssets,
+}
+
+/// Where footnote content is output
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FootnoteOutput {
+    /// numbered superscript links to a footnotes section, with back-references, after the content
+    #[default]
+    Section,
+
+    /// `Sidenote` component beside each reference, for wide-screen layouts
+    Sidenotes,
+}
+
+/// Typographic rule for paragraphs and table cells
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypographyRule {
+    /// curly quotes and apostrophes
+    Quotes,
+
+    /// `--` and `---` as en and em dashes
+    Dashes,
+
+    /// `...` as an ellipsis
+    Ellipses,
+
+    /// non-breaking space joining the last two words, so the last line is never a single word
+    Widows,
+
+    /// non-breaking space between a number and its unit, as in `35&nbsp;mm`
+    Units,
+
+    /// multiplication sign in dimensions, as in `6×9`
+    Dimensions,
+
+    /// thin-spaced en dash in number ranges, as in `10&thinsp;&ndash;&thinsp;20`
+    NumberRanges,
+
+    /// narrow non-breaking space before `:`, `;`, `!` and `?`, in French text
+    PunctuationSpaces,
+}
+
+impl FromStr for TypographyRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "quotes" => Ok(TypographyRule::Quotes),
+            "dashes" => Ok(TypographyRule::Dashes),
+            "ellipses" => Ok(TypographyRule::Ellipses),
+            "widows" => Ok(TypographyRule::Widows),
+            "units" => Ok(TypographyRule::Units),
+            "dimensions" => Ok(TypographyRule::Dimensions),
+            "ranges" => Ok(TypographyRule::NumberRanges),
+            "spacing" => Ok(TypographyRule::PunctuationSpaces),
+            _ => Err(format!(
+                "unknown typography rule `{value}`, expected one of quotes, dashes, ellipses, \
+widows, units, dimensions, ranges or spacing"
+            )),
+        }
     }
-    result.push(String::from(
-        "import LinkIcon from '~components/Icons/Link.svelte';
-import InlineCodeFragment from '~components/InlineCodeFragment.svelte';",
-    ));
-    if components.contains(&JSXComponentType::Poll) {
-        define_slug = true;
-        result.push(String::from("import Poll from '~components/Poll.svelte';"));
-    }
-    if components.contains(&JSXComponentType::Image) {
-        result.push(String::from(
-            "import type { NebulaPicture, PostPagePictures } from '~types/image';",
-        ));
-    } else if components.contains(&JSXComponentType::Video) {
-        result.push(String::from(
-            "import type { PostPagePictures } from '~types/image';",
-        ));
+}
+
+/// Language of a document, which sets its quotation marks and punctuation spacing
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Locale {
+    /// “…” and ‘…’
+    #[default]
+    English,
+
+    /// « … » and “…”, with narrow non-breaking spaces inside guillemets and before `:`, `;`, `!`
+    /// and `?`
+    French,
+
+    /// „…“ and ‚…‘
+    German,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    // language tag, such as `fr` or `de-AT`, matched on its primary language
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let language = value.split(['-', '_']).next().unwrap_or(value);
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Ok(Locale::English),
+            "fr" => Ok(Locale::French),
+            "de" => Ok(Locale::German),
+            _ => Err(format!(
+                "unsupported language `{value}`, expected one of en, fr or de"
+            )),
+        }
     }
-    if components.contains(&JSXComponentType::Questions) {
-        result.push(String::from(
-            "import Questions from '~components/Questions.svelte';",
-        ));
-        result.push(format!(
-            "import questions from '~content-raw/blog/{slug}/questions.json';"
-        ));
+}
+
+/// Units the `Units` typography rule keeps with a preceding number, unless others are chosen; `%`
+/// is left out, as English sets it against the number
+pub const DEFAULT_UNITS: [&str; 19] = [
+    "mm", "cm", "km", "mg", "kg", "ml", "ms", "px", "KB", "kB", "MB", "GB", "TB", "Hz", "kHz",
+    "MHz", "GHz", "°C", "°F",
+];
+
+/// Typographic rules applied to paragraphs and table cells; by default text is left as written
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Typography {
+    pub rules: Vec<TypographyRule>,
+
+    /// units for the `Units` rule
+    pub units: Vec<String>,
+
+    /// language of documents without a `lang` frontmatter field
+    pub locale: Locale,
+}
+
+impl Typography {
+    /// Every rule, with the default units
+    pub fn smart() -> Typography {
+        Typography {
+            rules: vec![
+                TypographyRule::Quotes,
+                TypographyRule::Dashes,
+                TypographyRule::Ellipses,
+                TypographyRule::Widows,
+                TypographyRule::Units,
+                TypographyRule::Dimensions,
+                TypographyRule::NumberRanges,
+                TypographyRule::PunctuationSpaces,
+            ],
+            units: DEFAULT_UNITS.iter().map(|unit| unit.to_string()).collect(),
+            locale: Locale::default(),
+        }
     }
-    if components.contains(&JSXComponentType::Tweet) {
-        result.push(String::from(
-            "import Tweet from '~components/Tweet.svelte';",
-        ));
+}
+
+/// Module the `Callout` component is imported from, unless another is chosen
+pub const DEFAULT_CALLOUT_COMPONENT: &str = "~components/Callout.svelte";
+
+/// Options for converting MDX source to Astro markup
+pub struct ConvertOptions {
+    /// post slug, used in generated imports and content collection queries
+    pub slug: String,
+
+    /// output for local Markdown images
+    pub images: ImageOutput,
+
+    /// output for footnotes
+    pub footnotes: FootnoteOutput,
+
+    /// punctuation of paragraphs and table cells
+    pub typography: Typography,
+
+    /// module the `Callout` component, for GitHub-style callouts, is imported from
+    pub callout_component: String,
+}
+
+/// Convert MDX source to Astro markup, without touching the file system
+pub fn convert_str(source: &str, options: &ConvertOptions) -> Result<AstroOutput, ParseError> {
+    convert_source(source, "<input>", options)
+}
+
+// `file` names the source in error reports
+fn convert_source(
+    source: &str,
+    file: &str,
+    options: &ConvertOptions,
+) -> Result<AstroOutput, ParseError> {
+    let mut document = parse_document(source, file)?;
+    number_local_images(&mut document, options.images);
+    let locale = frontmatter_locale(&document.frontmatter).unwrap_or(options.typography.locale);
+    if locale != Locale::English {
+        format_heading_typography(&mut document, locale);
+    }
+    if !options.typography.rules.is_empty() {
+        let typography = Typography {
+            locale,
+            ..options.typography.clone()
+        };
+        format_typography(&mut document, &typography);
     }
-    result.push(String::from(
-        "import TwitterMessageLink from '~components/Link/TwitterMessageLink.svelte';",
-    ));
-    if components.contains(&JSXComponentType::Video) {
-        define_slug = true;
-        image_data_imports.push(String::from("poster"));
-        result.push(String::from(
-            "import Video from '~components/Video.svelte';",
-        ));
+    if options.footnotes == FootnoteOutput::Sidenotes {
+        place_sidenotes(&mut document);
     }
-    if define_slug {
-        result.push("import website from '~configuration/website';".to_string());
-        result.push("\nconst { newsletterUrl } = website;".to_string());
-        result.push(format!("const slug = '{slug}';"));
-        if components.contains(&JSXComponentType::Image)
-            && components.contains(&JSXComponentType::Video)
-        {
-            result.push(
-                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
-const {
-  data: { pagePictures, pictures },
-}: { data: { pagePictures: PostPagePictures; pictures: NebulaPicture[] } } =
-  postImagesContentCollectionEntry;
-const {
-  poster: { src: poster },
-} = pagePictures;"
-                    .to_string(),
-            );
-            result.push(
-                "const imageProps = pictures.map((element, index) => ({
-  index,
-  ...element,
-  slug,
-}));"
-                    .to_string(),
-            );
-        } else if components.contains(&JSXComponentType::Image) {
-            result.push(
-                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
-const {
-  data: { pictures },
-}: { data: { pictures: NebulaPicture[] } } = postImagesContentCollectionEntry;"
-                    .to_string(),
-            );
-            result.push(
-                "const imageProps = pictures.map((element, index) => ({
-  index,
-  ...element,
-  slug,
-}));"
-                    .to_string(),
-            );
-        } else if components.contains(&JSXComponentType::Video) {
-            result.push(
-                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
-const {
-  data: { pagePictures },
-}: { data: { pagePictures: PostPagePictures; } } =
-  postImagesContentCollectionEntry;
-const {
-  poster: { src: poster },
-} = pagePictures;"
-                    .to_string(),
-            );
-        }
-    } else {
-        result.push("import website from '~configuration/website';".to_string());
-        result.push("\nconst { newsletterUrl } = website;".to_string());
-    }
-    if components.contains(&JSXComponentType::Tweet) {
-        result.push(
-            "const pageImagesContentCollectionEntry = await getEntry('page-images', 'blog');
-const {
-  data: { pagePictures: blogPagePictures },
-}: { data: { pagePictures: PostPagePictures } } = pageImagesContentCollectionEntry;
-const {
-  twitterAvatar: { src: avatarSrc, placeholder: avatarPlaceholder },
-} = blogPagePictures;"
-                .to_string(),
-        );
-    }
-    for line in prepared_markup {
-        result.push(line.to_string());
-    }
-    result.push(String::from("---\n"));
-    result
+    Ok(render_document(
+        &document,
+        &options.slug,
+        &options.callout_component,
+    ))
 }
 
-fn parse_frontmatter_delimiter(line: &str) -> IResult<&str, &str> {
-    let (line, _) = tag("---")(line)?;
-    Ok((line, ""))
+// a `lang` frontmatter field, in a supported language, overrides the chosen locale
+fn frontmatter_locale(frontmatter: &[(String, FrontmatterValue)]) -> Option<Locale> {
+    frontmatter.iter().find_map(|(key, value)| match value {
+        FrontmatterValue::String(language) if key == "lang" => language.parse().ok(),
+        _ => None,
+    })
 }
 
-fn parse_frontmatter_line(line: &str) -> (Option<String>, LineType) {
-    match parse_frontmatter_delimiter(line) {
-        Ok((_frontmatter_line, _)) => (None, LineType::FrontmatterDelimiter),
-        Err(_) => (Some(String::from(line)), LineType::Frontmatter),
-    }
+// remote images, and those served from the site root, are left as they are
+fn is_local_image(src: &str) -> bool {
+    parse_href_scheme(src).is_err()
+        && !src.starts_with('/')
+        && !src.starts_with("data:")
+        && !src.is_empty()
 }
 
-fn parse_open_markdown_block(
-    line: &str,
-    open_markdown_block: Option<&MarkdownBlock>,
-) -> Option<(String, LineType, usize)> {
-    match open_markdown_block {
-        Some(MarkdownBlock::OrderedList) => match form_ordered_list_line(line) {
-            Ok((_, (line, line_type, level))) => {
-                if line.is_empty() {
-                    Some((String::from("</ol>"), LineType::OrderedList, level))
-                } else {
-                    let markup = format!("</li>{line}");
-                    Some((markup, line_type, level))
+// copies footnote content to each reference, in place of the footnotes section
+fn place_sidenotes(document: &mut Document) {
+    fn place_inline_sidenotes(inlines: &mut [Inline], footnotes: &[Footnote]) {
+        for inline in inlines {
+            match inline {
+                Inline::FootnoteReference(reference) => {
+                    let children = footnotes[reference.number - 1].children.clone();
+                    reference.sidenote = Some(children);
                 }
+                Inline::Emphasis(content)
+                | Inline::Strikethrough(content)
+                | Inline::Strong(content)
+                | Inline::Link(Link { content, .. })
+                | Inline::LinkReference(LinkReference { content, .. }) => {
+                    place_inline_sidenotes(content, footnotes);
+                }
+                Inline::Code(_)
+                | Inline::Image(_)
+                | Inline::LineBreak
+                | Inline::SoftBreak
+                | Inline::Text(_) => {}
             }
-            Err(_) => None,
-        },
-        None => None,
+        }
+    }
+
+    let footnotes = std::mem::take(&mut document.footnotes);
+    for block in &mut document.blocks {
+        block.visit_inlines_mut(&mut |inlines| place_inline_sidenotes(inlines, &footnotes));
     }
 }
 
-fn parse_open_html_block(
-    line: &str,
-    open_html_block_elements: Option<&HTMLBlockElementType>,
-) -> Option<(String, LineType, usize)> {
-    match open_html_block_elements {
-        Some(HTMLBlockElementType::Div) => match form_html_block_element_last_line(line) {
-            Ok((_, (line, line_type, level))) => {
-                if line.is_empty() {
-                    None
-                } else {
-                    Some((line, line_type, level))
+// each local image file gets a single index, however many times it is used
+fn number_local_images(document: &mut Document, images: ImageOutput) {
+    fn number_inline_images(
+        inlines: &mut [Inline],
+        images: ImageOutput,
+        first_post_image: usize,
+        sources: &mut Vec<String>,
+    ) {
+        for inline in inlines {
+            match inline {
+                Inline::Image(image) if is_local_image(&image.src) => {
+                    let index = match sources.iter().position(|src| *src == image.src) {
+                        Some(value) => value,
+                        None => {
+                            sources.push(image.src.clone());
+                            sources.len() - 1
+                        }
+                    };
+                    image.local = Some(match images {
+                        ImageOutput::AstroAssets => LocalImage::AstroAsset(index),
+                        ImageOutput::PostImages => LocalImage::PostImage(first_post_image + index),
+                    });
                 }
-            }
-            Err(_) => Some((line.to_string(), LineType::HTMLDivBlockOpen, 0)),
-        },
-        Some(HTMLBlockElementType::Figure) => match form_html_block_element_last_line(line) {
-            Ok((_, (line, line_type, level))) => {
-                if line.is_empty() {
-                    None
-                } else {
-                    Some((line, line_type, level))
+                Inline::Emphasis(content)
+                | Inline::Strikethrough(content)
+                | Inline::Strong(content) => {
+                    number_inline_images(content, images, first_post_image, sources);
                 }
+                Inline::Link(Link { content, .. })
+                | Inline::LinkReference(LinkReference { content, .. }) => {
+                    number_inline_images(content, images, first_post_image, sources);
+                }
+                Inline::Code(_)
+                | Inline::FootnoteReference(_)
+                | Inline::Image(_)
+                | Inline::LineBreak
+                | Inline::SoftBreak
+                | Inline::Text(_) => {}
             }
-            Err(_) => Some((line.to_string(), LineType::HTMLFigureBlockOpen, 0)),
-        },
-        Some(HTMLBlockElementType::DescriptionList) => {
-            match form_html_block_element_last_line(line) {
-                Ok((_, (line, line_type, level))) => {
-                    if line.is_empty() {
-                        None
-                    } else {
-                        Some((line, line_type, level))
+        }
+    }
+
+    // Markdown images take the post-images pictures after those of hand-written `Image` components
+    let mut first_post_image = 0;
+    for block in document.blocks.iter_mut().chain(
+        document
+            .footnotes
+            .iter_mut()
+            .flat_map(|footnote| &mut footnote.children),
+    ) {
+        block.visit_blocks_mut(&mut |block| {
+            if let Block::JsxComponent(JsxComponent {
+                component: JSXComponentType::Image,
+                children,
+            }) = block
+            {
+                for child in children {
+                    if let Block::Markup(markup) = child {
+                        if let Ok((_, index)) = parse_image_component_index(markup) {
+                            first_post_image = first_post_image.max(index + 1);
+                        }
                     }
                 }
-                Err(_) => Some((line.to_string(), LineType::HTMLDescriptionListOpen, 0)),
             }
+        });
+    }
+
+    let mut sources: Vec<String> = Vec::new();
+    for block in document.blocks.iter_mut().chain(
+        document
+            .footnotes
+            .iter_mut()
+            .flat_map(|footnote| &mut footnote.children),
+    ) {
+        block.visit_inlines_mut(&mut |inlines| {
+            number_inline_images(inlines, images, first_post_image, &mut sources)
+        });
+    }
+    if images == ImageOutput::AstroAssets {
+        document.image_assets = sources;
+    }
+}
+
+// closes lists opened since the innermost open HTML block or JSX component
+fn close_lists(document: &mut DocumentBuilder, open_lists: &mut Stack<OpenList>) {
+    close_lists_to_indentation(document, open_lists, 0);
+}
+
+// closes open lists, from the innermost, until the line is indented into the last item content
+fn close_lists_to_indentation(
+    document: &mut DocumentBuilder,
+    open_lists: &mut Stack<OpenList>,
+    indentation: usize,
+) {
+    while let Some(Block::List(_)) = document.peek() {
+        if open_lists
+            .peek()
+            .is_some_and(|open_list| open_list.content_indentation <= indentation)
+        {
+            return;
         }
-        Some(HTMLBlockElementType::TableBody) => match form_table_body_last_line(line) {
-            Ok((_, value)) => Some(value),
-            Err(_) => None,
-        },
-        Some(HTMLBlockElementType::TableHead) => match form_table_head_last_line(line) {
-            Ok((_, value)) => Some(value),
-            Err(_) => None,
-        },
-        Some(HTMLBlockElementType::Comment) => {
-            match form_html_block_level_comment_last_line(line) {
-                Ok((_, value)) => Some(value),
-                Err(_) => None,
-            }
+        open_lists.pop();
+        document.close();
+    }
+}
+
+/* open list an item, with the given indentation, would be added to as a sibling of the last item;
+ * items indented to the content of the last item start a nested list instead
+ */
+fn sibling_list(open_lists: &Stack<OpenList>, indentation: usize) -> Option<&OpenList> {
+    for open_list in open_lists.iter().rev() {
+        if indentation >= open_list.content_indentation {
+            return None;
+        }
+        if indentation >= open_list.indentation {
+            return Some(open_list);
         }
-        None => None,
     }
+    None
 }
 
-fn parse_mdx_lines<B>(
-    line: &str,
-    lines_iterator: std::io::Lines<B>,
-    open_markdown_block: Option<&MarkdownBlock>,
-    open_html_block_elements: Option<&HTMLBlockElementType>,
-    open_jsx_component_register: &mut JSXComponentRegister,
-) -> (std::io::Lines<B>, Option<(String, LineType, usize)>)
-where
-    B: BufRead,
-{
-    match parse_open_markdown_block(line, open_markdown_block) {
-        Some(value) => (lines_iterator, Some(value)),
-        None => match parse_open_html_block(line, open_html_block_elements) {
-            Some((_parsed_line, LineType::HTMLDivBlockOpen, _indentation)) => {
-                (lines_iterator, parse_mdx_line(line))
+/* merges a single item list, parsed from a line, into the open lists; a list with a blank line
+ * between its items, or between blocks of an item, is loose
+ */
+fn add_list_item(
+    document: &mut DocumentBuilder,
+    open_lists: &mut Stack<OpenList>,
+    list: List,
+    open_list: OpenList,
+    after_blank_line: bool,
+) {
+    loop {
+        let innermost_list = match document.peek() {
+            Some(Block::List(_)) => open_lists.peek().copied(),
+            _ => None,
+        };
+        match innermost_list {
+            Some(innermost_list) if open_list.indentation >= innermost_list.content_indentation => {
+                break;
             }
-            Some(value) => (lines_iterator, Some(value)),
-            None => match parse_open_jsx_block(line, open_jsx_component_register) {
-                Some(value) => (lines_iterator, Some(value)),
-                None => (lines_iterator, parse_mdx_line(line)),
-            },
-        },
+            Some(innermost_list)
+                if open_list.indentation >= innermost_list.indentation
+                    && open_list.marker == innermost_list.marker =>
+            {
+                if let Some(Block::List(parent_list)) = document.peek_mut() {
+                    parent_list.loose |= after_blank_line;
+                    parent_list.items.extend(list.items);
+                }
+                open_lists.pop();
+                open_lists.push(open_list);
+                return;
+            }
+
+            // a change of bullet or delimiter starts a new list
+            Some(_) => {
+                open_lists.pop();
+                document.close();
+            }
+            None => break,
+        }
     }
+    if let Some(Block::List(parent_list)) = document.peek_mut() {
+        parent_list.loose |= after_blank_line;
+    }
+    open_lists.push(open_list);
+    document.open(Block::List(list));
+}
+
+// adds the open paragraph to the innermost open block, or to the document
+fn close_paragraph(
+    document: &mut DocumentBuilder,
+    paragraph_lines: &mut Vec<(usize, &str)>,
+    link_references: &mut Vec<(usize, LinkReference)>,
+    file: &str,
+    source: &str,
+) -> Result<(), ParseError> {
+    if paragraph_lines.is_empty() {
+        return Ok(());
+    }
+    let content = parse_paragraph(paragraph_lines, file, source)?;
+    let mut references: Vec<LinkReference> = Vec::new();
+    collect_link_references(&content, &mut references);
+    for reference in references {
+        let line_index = paragraph_lines
+            .iter()
+            .find(|(_, line_content)| line_content.contains(&reference.source))
+            .map_or(paragraph_lines[0].0, |(line_index, _)| *line_index);
+        link_references.push((line_index, reference));
+    }
+    paragraph_lines.clear();
+    document.push(Block::Paragraph(content));
+    Ok(())
 }
 
-fn parse_mdx_line(line: &str) -> Option<(String, LineType, usize)> {
-    match alt((
-        form_code_fragment_component_first_line,
-        form_fenced_code_block_first_line,
-        // form_how_to_component_first_line,
-        form_html_block_level_comment_first_line,
-        form_html_block_element_first_line,
-        form_table_head_first_line,
-        form_image_component,
-        form_poll_component_first_line,
-        form_questions_component,
-        form_tweet_component,
-        form_gatsby_not_maintained_component,
-        form_video_component_first_line,
-        form_heading_line,
-        form_ordered_list_first_line,
-        form_unordered_list_line,
-        form_inline_wrap_text,
-    ))
-    .parse(line)
-    {
-        Ok((_, (line, line_type, level))) => {
-            if line.is_empty() {
-                None
-            } else {
-                Some((line, line_type, level))
+// closes the innermost blockquote, along with any blocks still open inside it
+fn close_block_quote(
+    document: &mut DocumentBuilder,
+    open_lists: &mut Stack<OpenList>,
+    open_html_block_element_stack: &mut Stack<HTMLBlockElementType>,
+    open_jsx_component_register: &mut JSXComponentRegister,
+) {
+    loop {
+        match document.peek() {
+            Some(Block::List(_)) => {
+                close_lists(document, open_lists);
+            }
+            Some(Block::CodeBlock(_) | Block::JsxComponent(_)) => {
+                // indented code blocks are not registered
+                if open_jsx_component_register.code_fence().is_some()
+                    || matches!(document.peek(), Some(Block::JsxComponent(_)))
+                {
+                    open_jsx_component_register.pop();
+                }
+                document.close();
             }
+            Some(Block::BlockQuote(_)) => {
+                open_html_block_element_stack.pop();
+                document.close();
+                return;
+            }
+            Some(_) => {
+                open_html_block_element_stack.pop();
+                document.close();
+            }
+            None => return,
         }
-        Err(_) => None,
     }
 }
 
-pub fn parse_frontmatter(file: &File) -> usize {
-    let reader = BufReader::new(file);
-    let mut frontmatter_open = false;
-    let mut line_number = 1;
-
-    for line in reader.lines() {
-        let line_content = line.unwrap();
-        let (_frontmatter_line_option, line_type) = parse_frontmatter_line(&line_content);
-        if line_type == LineType::FrontmatterDelimiter {
-            frontmatter_open = !frontmatter_open;
-            if !frontmatter_open {
-                return line_number;
-            }
-        } else if !frontmatter_open {
-            /* first line of file (with content) is not frontmatter delimiter so assume there is no
-             * frontmatter
-             */
-            return 0;
-        };
-        line_number += 1;
+fn open_block_quote(
+    document: &mut DocumentBuilder,
+    open_html_block_element_stack: &mut Stack<HTMLBlockElementType>,
+    callout: Option<CalloutType>,
+) {
+    // a table cannot contain a blockquote
+    if let Some(Block::Table(_)) = document.peek() {
+        open_html_block_element_stack.pop();
+        document.close();
+    }
+    open_html_block_element_stack.push(HTMLBlockElementType::BlockQuote);
+    document.open(Block::BlockQuote(BlockQuote {
+        callout,
+        children: Vec::new(),
+    }));
+}
+
+// adds a line to the open component, or opens a new one
+fn add_jsx_component_line(
+    document: &mut DocumentBuilder,
+    component: JSXComponentType,
+    block: Block,
+) {
+    match document.peek() {
+        Some(Block::JsxComponent(open_component)) if open_component.component == component => {
+            document.push(block);
+        }
+        _ => document.open(Block::JsxComponent(JsxComponent {
+            component,
+            children: vec![block],
+        })),
     }
-    line_number
 }
 
-pub fn slug_from_input_file_path<P: AsRef<Path>>(path: &P) -> &str {
-    match path
-        .as_ref()
-        .file_stem()
-        .expect("[ ERROR ] Couldn't open that file!")
-        .to_str()
-    {
-        Some(value) => match value {
-            "index" => path
-                .as_ref()
-                .parent()
-                .expect("[ ERROR ] Couldn't open that file!")
-                .file_name()
-                .expect("[ ERROR ] Couldn't open that file!")
-                .to_str()
-                .expect("[ ERROR ] Couldn't open that file!"),
-            other => other,
-        },
-        None => panic!("[ ERROR ] Couldn't open that file!"),
+/* closes the open component with its last line, returning true, or adds a self-closing component
+ * and returns false
+ */
+fn add_jsx_component_last_line(
+    document: &mut DocumentBuilder,
+    component: JSXComponentType,
+    block: Block,
+) -> bool {
+    match document.peek() {
+        Some(Block::JsxComponent(open_component)) if open_component.component == component => {
+            document.push(block);
+            document.close();
+            true
+        }
+        _ => {
+            document.push(Block::JsxComponent(JsxComponent {
+                component,
+                children: vec![block],
+            }));
+            false
+        }
     }
 }
 
-pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
-    input_path: &P1,
-    output_path: &P2,
-    verbose: bool,
+fn add_html_block_line(
+    document: &mut DocumentBuilder,
+    element: HTMLBlockElementType,
+    block: Block,
 ) {
-    println!(
-        "[ INFO ] Parsing {:?}...",
-        input_path.as_ref().display().to_string()
-    );
-    let start = Instant::now();
-
-    let file = File::open(input_path).expect("[ ERROR ] Couldn't open that file!");
-    let frontmatter_end_line_number = parse_frontmatter(&file);
-    let file = File::open(input_path).expect("[ ERROR ] Couldn't open that file!");
+    match document.peek() {
+        Some(Block::HtmlBlock(open_block)) if open_block.element == element => {
+            document.push(block);
+        }
+        _ => document.open(Block::HtmlBlock(HtmlBlock {
+            element,
+            children: vec![block],
+        })),
+    }
+}
 
-    let slug = slug_from_input_file_path(input_path);
-    let mut tokens: Vec<String> = Vec::new();
-    let reader = BufReader::new(&file);
+// builds the document tree, passing each line through the open block state machines
+fn parse_document(source: &str, file: &str) -> Result<Document, ParseError> {
+    let frontmatter_end_line_number = parse_frontmatter(source);
+    let frontmatter = parse_frontmatter_fields(source, file, frontmatter_end_line_number)?;
 
-    let mut current_indentation: usize = 0;
-    let mut open_lists = Stack::new();
+    let mut document = DocumentBuilder::new();
+    let mut open_lists: Stack<OpenList> = Stack::new();
 
     // used to keep a track of open blocks
     let mut open_jsx_component_register = JSXComponentRegister::new();
     let mut open_html_block_element_stack: Stack<HTMLBlockElementType> = Stack::new();
-    let mut open_markdown_block_stack: Stack<MarkdownBlock> = Stack::new();
-    let mut astro_frontmatter_markup: Vec<String> = Vec::new();
+    let mut declarations: Vec<String> = Vec::new();
 
-    let mut present_jsx_component_types: HashSet<JSXComponentType> = HashSet::new();
+    // definitions may follow the references, which are only resolved once every line is parsed
+    let mut link_definitions: HashMap<String, LinkDefinition> = HashMap::new();
+    let mut link_references: Vec<(usize, LinkReference)> = Vec::new();
 
-    let mut lines_iterator = reader.lines();
-    if frontmatter_end_line_number > 0 {
-        lines_iterator.nth(frontmatter_end_line_number - 1); // discard frontmatter
-    }
-    while let Some(line) = lines_iterator.next() {
-        let line_content = line.unwrap();
+    // paragraph text is only parsed once the paragraph ends, so inline markup can span lines
+    let mut paragraph_lines: Vec<(usize, &str)> = Vec::new();
+    let mut footnote_definition_open = false;
+    let mut indented_code_blank_lines = 0;
 
-        let (lines_iterator_current, parsed_line) = parse_mdx_lines(
-            &line_content,
-            lines_iterator,
-            open_markdown_block_stack.peek(),
-            open_html_block_element_stack.peek(),
-            &mut open_jsx_component_register,
-        );
-        lines_iterator = lines_iterator_current;
-        match parsed_line {
-            Some((line, line_type, indentation)) => match line_type {
-                LineType::OrderedList => {
-                    open_markdown_block_stack.pop();
-                    open_lists.pop();
-                    tokens.push(line);
-                }
-                LineType::OrderedListItemOpen => {
-                    let open_markdown_block = open_markdown_block_stack.peek();
-                    if open_markdown_block != Some(&MarkdownBlock::OrderedList) {
-                        open_markdown_block_stack.push(MarkdownBlock::OrderedList);
-                    }
-                    if open_lists.is_empty() {
-                        open_lists.push(ListType::Ordered);
-                        tokens.push(line);
-                    } else if indentation > current_indentation {
-                        open_lists.push(ListType::Ordered);
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("<ol>\n  {list_item_indentation}{line}"));
-                    } else if indentation == current_indentation {
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("{list_item_indentation}{line}"));
-                    } else {
-                        while open_lists.pop() != Some(ListType::Ordered) {
-                            tokens.push(String::from("</ul>"));
-                        }
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("</ol>\n{list_item_indentation}{line}"));
-                        open_markdown_block_stack.pop();
-                    }
-                    current_indentation = indentation;
+    let source_lines: Vec<&str> = source.lines().collect();
+
+    // discard frontmatter
+    for (line_index, line_content) in source.lines().enumerate().skip(frontmatter_end_line_number) {
+        let open_block_quote_depth = open_html_block_element_stack
+            .iter()
+            .filter(|element| **element == HTMLBlockElementType::BlockQuote)
+            .count();
+
+        // code and comment content may start with `>`, so only markers of open quotes are stripped
+        let max_block_quote_depth = if open_jsx_component_register.code_fence().is_some()
+            || open_html_block_element_stack.peek() == Some(&HTMLBlockElementType::Comment)
+        {
+            open_block_quote_depth
+        } else {
+            usize::MAX
+        };
+        let (block_quote_depth, line_content) =
+            strip_block_quote_markers(line_content, max_block_quote_depth);
+        let after_blank_line = line_index > 0
+            && strip_block_quote_markers(source_lines[line_index - 1], usize::MAX)
+                .1
+                .trim()
+                .is_empty();
+
+        // blank lines, and lines indented by four spaces, continue an open footnote definition
+        let footnote_continuation = footnote_definition_open
+            && (line_content.trim().is_empty()
+                || line_content.starts_with("    ")
+                || line_content.starts_with('\t'));
+        let line_content = if footnote_continuation {
+            line_content
+                .strip_prefix("    ")
+                .or_else(|| line_content.strip_prefix('\t'))
+                .unwrap_or(line_content)
+        } else {
+            line_content
+        };
+
+        if block_quote_depth > open_block_quote_depth {
+            if let Ok((_, callout)) = parse_callout_first_line(line_content) {
+                close_paragraph(
+                    &mut document,
+                    &mut paragraph_lines,
+                    &mut link_references,
+                    file,
+                    source,
+                )?;
+
+                // the callout ends an open indented code block or list
+                if open_jsx_component_register.code_fence().is_none()
+                    && matches!(document.peek(), Some(Block::CodeBlock(_)))
+                {
+                    indented_code_blank_lines = 0;
+                    document.close();
                 }
-                LineType::UnorderedListItem => {
-                    if open_lists.is_empty() {
-                        open_lists.push(ListType::Unordered);
-                        tokens.push(format!("<ul>\n  {line}"));
-                    } else if indentation > current_indentation {
-                        open_lists.push(ListType::Unordered);
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("<ul>\n{list_item_indentation}{line}"));
-                    } else if indentation == current_indentation {
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("{list_item_indentation}{line}"));
-                    } else {
-                        while open_lists.pop() != Some(ListType::Unordered) {
-                            tokens.push(String::from("</ol>"));
-                            open_markdown_block_stack.pop();
-                        }
-                        let list_item_indentation = " ".repeat(2 * open_lists.len());
-                        tokens.push(format!("</ul>\n{list_item_indentation}{line}"));
+                close_lists(&mut document, &mut open_lists);
+                if footnote_definition_open {
+                    if let Some(Block::FootnoteDefinition(_)) = document.peek() {
+                        document.close();
                     }
-                    current_indentation = indentation;
+                    footnote_definition_open = false;
                 }
-                LineType::Poll => {
-                    present_jsx_component_types.insert(JSXComponentType::Poll);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+                for depth in open_block_quote_depth..block_quote_depth {
+                    let innermost = depth + 1 == block_quote_depth;
+                    open_block_quote(
+                        &mut document,
+                        &mut open_html_block_element_stack,
+                        if innermost { Some(callout) } else { None },
+                    );
                 }
-                LineType::Video => {
-                    present_jsx_component_types.insert(JSXComponentType::Video);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+                continue;
+            }
+        }
+
+        /* list item content is indented to the content column of the item, and code lines lose up to
+         * that indentation
+         */
+        let line_indentation = line_content.len() - line_content.trim_start_matches(' ').len();
+        let list_content_indentation = if matches!(
+            document.peek(),
+            Some(Block::BlockQuote(_) | Block::FootnoteDefinition(_))
+        ) {
+            0
+        } else if open_jsx_component_register.code_fence().is_some() {
+            open_lists.peek().map_or(0, |open_list| {
+                open_list.content_indentation.min(line_indentation)
+            })
+        } else {
+            open_lists
+                .iter()
+                .map(|open_list| open_list.content_indentation)
+                .filter(|content_indentation| *content_indentation <= line_indentation)
+                .max()
+                .unwrap_or(0)
+        };
+        let line_content = &line_content[list_content_indentation..];
+
+        // an indented code block runs over blank lines, which are only kept when more code follows
+        if open_jsx_component_register.code_fence().is_none()
+            && matches!(document.peek(), Some(Block::CodeBlock(_)))
+        {
+            if block_quote_depth == open_block_quote_depth {
+                if line_content.trim().is_empty() {
+                    indented_code_blank_lines += 1;
+                    continue;
                 }
-                LineType::FencedCodeBlock | LineType::CodeFragment => {
-                    present_jsx_component_types.insert(JSXComponentType::CodeFragment);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+                if let Ok((_, code)) = parse_indented_code_line(line_content) {
+                    for _ in 0..indented_code_blank_lines {
+                        document.push(Block::Markup(String::new()));
+                    }
+                    indented_code_blank_lines = 0;
+                    document.push(Block::Markup(code.to_string()));
+                    continue;
                 }
-                LineType::HowTo => {
-                    present_jsx_component_types.insert(JSXComponentType::HowTo);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+            }
+            indented_code_blank_lines = 0;
+            document.close();
+        }
 
-                    if let Some(value) = open_jsx_component_register.how_to() {
-                        astro_frontmatter_markup.append(&mut value.astro_frontmatter_markup());
-                    };
+        // indented code cannot interrupt a paragraph, and indented HTML or JSX is not code
+        if paragraph_lines.is_empty()
+            && block_quote_depth == open_block_quote_depth
+            && open_jsx_component_register.peek().is_none()
+            && matches!(
+                open_html_block_element_stack.peek(),
+                None | Some(HTMLBlockElementType::BlockQuote)
+            )
+        {
+            if let Ok((_, code)) = parse_indented_code_line(line_content) {
+                // like any other block of a list item, code after a blank line makes the list loose
+                if let Some(Block::List(_)) = document.peek() {
+                    close_lists_to_indentation(&mut document, &mut open_lists, line_indentation);
+                    if after_blank_line {
+                        if let Some(Block::List(list)) = document.peek_mut() {
+                            list.loose = true;
+                        }
+                    }
                 }
-                LineType::HowToSection => {
-                    present_jsx_component_types.insert(JSXComponentType::HowToSection);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+                document.open(Block::CodeBlock(CodeBlock {
+                    lines: vec![code.to_string()],
+                    ..Default::default()
+                }));
+                continue;
+            }
+        }
+
+        // a table header row is only recognised with the delimiter row following in the same quote
+        let next_line = source_lines
+            .get(line_index + 1)
+            .map(|next_line| strip_block_quote_markers(next_line, block_quote_depth))
+            .filter(|(next_line_depth, _)| *next_line_depth == block_quote_depth)
+            .map(|(_, next_line)| {
+                let next_line_indentation =
+                    next_line.len() - next_line.trim_start_matches(' ').len();
+                &next_line[list_content_indentation.min(next_line_indentation)..]
+            });
+        let mut parsed_line = parse_mdx_lines(
+            line_content,
+            next_line,
+            open_html_block_element_stack.peek(),
+            &mut open_jsx_component_register,
+        )
+        .map_err(|error| {
+            ParseError::from_line_error(
+                &error,
+                file,
+                source,
+                source_lines[line_index],
+                line_index + 1,
+            )
+        })?;
+
+        // any line other than a table row ends an open table
+        if let Some(Block::Table(_)) = document.peek() {
+            if !matches!(
+                parsed_line,
+                Some((
+                    _,
+                    LineType::HTMLTableHeadOpen
+                        | LineType::HTMLTableBodyOpen
+                        | LineType::HTMLTableBody,
+                    _
+                ))
+            ) {
+                open_html_block_element_stack.pop();
+                document.close();
+            }
+        }
+
+        // an ordered list may only interrupt a paragraph when it starts at one
+        if let Some((
+            Block::List(List {
+                start: Some(start), ..
+            }),
+            LineType::OrderedListItemOpen,
+            indentation,
+        )) = &parsed_line
+        {
+            let line_indentation = list_content_indentation + indentation;
+            let continues_list = match (
+                sibling_list(&open_lists, line_indentation),
+                parse_list_item_marker(line_content),
+            ) {
+                (Some(open_list), Ok((_, marker))) => open_list.marker == marker,
+                _ => false,
+            };
+            // the paragraph only continues when the line is indented into its list item
+            let continues_paragraph = open_lists.peek().map_or(true, |open_list| {
+                line_indentation >= open_list.content_indentation
+            });
+            if !paragraph_lines.is_empty() && start != "1" && !continues_list && continues_paragraph
+            {
+                parsed_line = Some((Block::Paragraph(Vec::new()), LineType::Paragraph, 0));
+            }
+        }
+
+        /* an underline directly under paragraph text, in the same container, turns the paragraph
+         * into a setext heading, rather than continuing it or breaking it
+         */
+        if !paragraph_lines.is_empty()
+            && matches!(
+                parsed_line,
+                Some((_, LineType::Paragraph | LineType::ThematicBreak, _))
+            )
+        {
+            let continues_paragraph = block_quote_depth == open_block_quote_depth
+                && open_lists.peek().map_or(true, |open_list| {
+                    line_indentation >= open_list.content_indentation
+                });
+            if let (true, Ok((_, level))) = (
+                continues_paragraph,
+                parse_setext_heading_underline(line_content),
+            ) {
+                parsed_line = Some(form_setext_heading(&paragraph_lines, level));
+                paragraph_lines.clear();
+            }
+        }
+
+        /* other than a list item or lazy paragraph text, a line must be indented into the list item;
+         * a line opening a blockquote is never lazy text
+         */
+        let lazy_list_item_line = block_quote_depth <= open_block_quote_depth
+            && !paragraph_lines.is_empty()
+            && matches!(parsed_line, Some((_, LineType::Paragraph, _)));
+        if !lazy_list_item_line
+            && !matches!(
+                parsed_line,
+                None | Some((
+                    _,
+                    LineType::OrderedListItemOpen | LineType::UnorderedListItem,
+                    _
+                ))
+            )
+            && matches!(document.peek(), Some(Block::List(_)))
+        {
+            close_paragraph(
+                &mut document,
+                &mut paragraph_lines,
+                &mut link_references,
+                file,
+                source,
+            )?;
+            close_lists_to_indentation(&mut document, &mut open_lists, line_indentation);
+            if after_blank_line {
+                if let Some(Block::List(list)) = document.peek_mut() {
+                    list.loose = true;
                 }
-                LineType::HowToStep => {
-                    present_jsx_component_types.insert(JSXComponentType::HowToStep);
-                    open_jsx_component_register.pop();
-                    tokens.push(line);
+            }
+        }
+
+        /* any other line ends the footnote definition, unless it lazily continues a paragraph, so a
+         * blockquote opening after it is not nested inside it
+         */
+        let lazy_paragraph_line = block_quote_depth <= open_block_quote_depth
+            && !paragraph_lines.is_empty()
+            && matches!(parsed_line, Some((_, LineType::Paragraph, _)));
+        if footnote_definition_open
+            && !footnote_continuation
+            && !lazy_paragraph_line
+            && open_jsx_component_register.code_fence().is_none()
+        {
+            close_paragraph(
+                &mut document,
+                &mut paragraph_lines,
+                &mut link_references,
+                file,
+                source,
+            )?;
+            close_lists(&mut document, &mut open_lists);
+            if let Some(Block::FootnoteDefinition(_)) = document.peek() {
+                document.close();
+            }
+            footnote_definition_open = false;
+        }
+
+        // a paragraph line, without all the markers of the open quotes, lazily continues the quote
+        let lazy_continuation = block_quote_depth < open_block_quote_depth
+            && !paragraph_lines.is_empty()
+            && matches!(parsed_line, Some((_, LineType::Paragraph, _)));
+        if block_quote_depth != open_block_quote_depth && !lazy_continuation {
+            close_paragraph(
+                &mut document,
+                &mut paragraph_lines,
+                &mut link_references,
+                file,
+                source,
+            )?;
+            for _ in block_quote_depth..open_block_quote_depth {
+                close_block_quote(
+                    &mut document,
+                    &mut open_lists,
+                    &mut open_html_block_element_stack,
+                    &mut open_jsx_component_register,
+                );
+            }
+            for _ in open_block_quote_depth..block_quote_depth {
+                open_block_quote(&mut document, &mut open_html_block_element_stack, None);
+            }
+        }
+
+        // a link reference definition cannot interrupt a paragraph
+        let paragraph_line = match parsed_line {
+            Some((_, LineType::Paragraph, _)) => true,
+            Some((_, LineType::LinkReferenceDefinition, _)) => !paragraph_lines.is_empty(),
+            _ => false,
+        };
+        if paragraph_line {
+            paragraph_lines.push((line_index, line_content));
+            continue;
+        }
+        close_paragraph(
+            &mut document,
+            &mut paragraph_lines,
+            &mut link_references,
+            file,
+            source,
+        )?;
+        if let Some((block, _, _)) = &mut parsed_line {
+            let mut references: Vec<LinkReference> = Vec::new();
+            block.visit_inlines_mut(&mut |inlines| {
+                collect_link_references(inlines, &mut references)
+            });
+            link_references.extend(
+                references
+                    .into_iter()
+                    .map(|reference| (line_index, reference)),
+            );
+        }
+
+        // blank lines leave lists open, as the following line may continue the list item
+        if let Some((block, line_type, indentation)) = parsed_line {
+            match line_type {
+                LineType::OrderedListItemOpen | LineType::UnorderedListItem => {
+                    if let (Block::List(list), Ok((list_text, marker))) =
+                        (block, parse_list_item_marker(line_content))
+                    {
+                        let open_list = OpenList {
+                            marker,
+                            indentation: list_content_indentation + indentation,
+                            content_indentation: list_content_indentation + line_content.len()
+                                - list_text.len(),
+                        };
+                        add_list_item(
+                            &mut document,
+                            &mut open_lists,
+                            list,
+                            open_list,
+                            after_blank_line,
+                        );
+                        if let Ok((item_text, _)) = form_list_item(list_text) {
+                            if !item_text.trim().is_empty() {
+                                paragraph_lines.push((line_index, item_text));
+                            }
+                        }
+                    }
                 }
-                LineType::HowToDirection => {
-                    present_jsx_component_types.insert(JSXComponentType::HowToDirection);
+                LineType::FencedCodeBlock => {
+                    // closing fence is not part of the code
+                    document.close();
                     open_jsx_component_register.pop();
-                    tokens.push(line);
                 }
-                LineType::Image => {
-                    present_jsx_component_types.insert(JSXComponentType::Image);
-                    tokens.push(line);
-                }
-                LineType::Questions => {
-                    present_jsx_component_types.insert(JSXComponentType::Questions);
-                    tokens.push(line);
-                }
-                LineType::GatsbyNotMaintained => {
-                    present_jsx_component_types.insert(JSXComponentType::GatsbyNotMaintained);
-                    tokens.push(line);
-                }
-                LineType::Tweet => {
-                    present_jsx_component_types.insert(JSXComponentType::Tweet);
-                    tokens.push(line);
+                LineType::Poll
+                | LineType::Video
+                | LineType::CodeFragment
+                | LineType::HowTo
+                | LineType::HowToSection
+                | LineType::HowToStep
+                | LineType::HowToDirection => {
+                    let component = match line_type {
+                        LineType::Poll => JSXComponentType::Poll,
+                        LineType::Video => JSXComponentType::Video,
+                        LineType::CodeFragment => JSXComponentType::CodeFragment,
+                        LineType::HowTo => JSXComponentType::HowTo,
+                        LineType::HowToSection => JSXComponentType::HowToSection,
+                        LineType::HowToStep => JSXComponentType::HowToStep,
+                        _ => JSXComponentType::HowToDirection,
+                    };
+                    if add_jsx_component_last_line(&mut document, component, block) {
+                        open_jsx_component_register.pop();
+                    }
+
+                    if line_type == LineType::HowTo {
+                        if let Some(value) = open_jsx_component_register.how_to() {
+                            declarations.append(&mut value.astro_frontmatter_markup());
+                        };
+                    }
                 }
+                LineType::Image => document.push(Block::JsxComponent(JsxComponent {
+                    component: JSXComponentType::Image,
+                    children: vec![block],
+                })),
+                LineType::Questions => document.push(Block::JsxComponent(JsxComponent {
+                    component: JSXComponentType::Questions,
+                    children: vec![block],
+                })),
+                // the outline is only complete once every line is parsed
+                LineType::TableOfContents => document.push(Block::JsxComponent(JsxComponent {
+                    component: JSXComponentType::TableOfContents,
+                    children: Vec::new(),
+                })),
+                LineType::GatsbyNotMaintained => document.push(Block::JsxComponent(JsxComponent {
+                    component: JSXComponentType::GatsbyNotMaintained,
+                    children: vec![block],
+                })),
+                LineType::Tweet => document.push(Block::JsxComponent(JsxComponent {
+                    component: JSXComponentType::Tweet,
+                    children: vec![block],
+                })),
                 LineType::HTMLBlockLevelComment
                 | LineType::HTMLDescriptionList
                 | LineType::HTMLDivBlock
-                | LineType::HTMLFigureBlock
-                | LineType::HTMLTableBody => {
+                | LineType::HTMLFigureBlock => {
                     open_html_block_element_stack.pop();
-                    tokens.push(line);
+                    close_lists(&mut document, &mut open_lists);
+                    document.push(block);
+                    if let Some(Block::HtmlBlock(_)) = document.peek() {
+                        document.close();
+                    }
                 }
-                LineType::FencedCodeBlockOpen => {
-                    if open_jsx_component_register.peek()
-                        != Some(&JSXComponentType::FencedCodeBlock)
+                LineType::HTMLTableBody => {
+                    open_html_block_element_stack.pop();
+                    document.close();
+                }
+                LineType::FootnoteDefinitionOpen => {
+                    close_lists(&mut document, &mut open_lists);
+                    document.open(block);
+                    footnote_definition_open = true;
+
+                    // text after the label starts the first paragraph
+                    if let Ok((text, _)) = parse_footnote_definition(line_content) {
+                        if !text.trim().is_empty() {
+                            paragraph_lines.push((line_index, text));
+                        }
+                    }
+                }
+                LineType::LinkReferenceDefinition => {
+                    if let Ok((_, (label, definition))) =
+                        parse_link_reference_definition(line_content)
                     {
-                        open_jsx_component_register.push(JSXComponentType::FencedCodeBlock);
+                        // the first definition of a label takes precedence
+                        link_definitions.entry(label).or_insert(definition);
+                    }
+                }
+                LineType::FencedCodeBlockOpen => {
+                    if open_jsx_component_register.code_fence().is_none() {
+                        if let Ok((_, fence)) = parse_code_fence(line_content) {
+                            open_jsx_component_register
+                                .push(JSXComponentType::FencedCodeBlock(fence));
+                        }
+                    }
+                    match block {
+                        Block::CodeBlock(_) => document.open(block),
+                        _ => document.push(block),
                     }
-                    tokens.push(line);
                 }
                 LineType::CodeFragmentOpen => {
                     if open_jsx_component_register.peek() != Some(&JSXComponentType::CodeFragment) {
                         open_jsx_component_register.push(JSXComponentType::CodeFragment);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::CodeFragment, block);
                 }
                 LineType::CodeFragmentOpening => {
                     if open_jsx_component_register.peek()
@@ -1510,7 +3181,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_jsx_component_register.push(JSXComponentType::CodeFragmentOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::CodeFragment, block);
                 }
                 LineType::HowToOpen => {
                     let current_open_jsx_component = open_jsx_component_register.peek();
@@ -1520,13 +3191,13 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     } else if current_open_jsx_component != Some(&JSXComponentType::HowTo) {
                         open_jsx_component_register.push(JSXComponentType::HowTo);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowTo, block);
                 }
                 LineType::HowToOpening => {
                     if open_jsx_component_register.peek() != Some(&JSXComponentType::HowToOpening) {
                         open_jsx_component_register.push(JSXComponentType::HowToOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowTo, block);
                 }
                 LineType::HowToSectionOpen => {
                     let current_open_jsx_component = open_jsx_component_register.peek();
@@ -1536,7 +3207,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     } else if current_open_jsx_component != Some(&JSXComponentType::HowToSection) {
                         open_jsx_component_register.push(JSXComponentType::HowToSection);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToSection, block);
                 }
                 LineType::HowToSectionOpening => {
                     if open_jsx_component_register.peek()
@@ -1544,7 +3215,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_jsx_component_register.push(JSXComponentType::HowToSectionOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToSection, block);
                 }
                 LineType::HowToStepOpen => {
                     let current_open_jsx_component = open_jsx_component_register.peek();
@@ -1554,7 +3225,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     } else if current_open_jsx_component != Some(&JSXComponentType::HowToStep) {
                         open_jsx_component_register.push(JSXComponentType::HowToStep);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToStep, block);
                 }
                 LineType::HowToStepOpening => {
                     if open_jsx_component_register.peek()
@@ -1562,7 +3233,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_jsx_component_register.push(JSXComponentType::HowToStepOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToStep, block);
                 }
                 LineType::HowToDirectionOpen => {
                     let current_open_jsx_component = open_jsx_component_register.peek();
@@ -1574,7 +3245,7 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_jsx_component_register.push(JSXComponentType::HowToDirection);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToDirection, block);
                 }
                 LineType::HowToDirectionOpening => {
                     if open_jsx_component_register.peek()
@@ -1582,20 +3253,19 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_jsx_component_register.push(JSXComponentType::HowToDirectionOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::HowToDirection, block);
                 }
                 LineType::PollOpen => {
-                    present_jsx_component_types.insert(JSXComponentType::Poll);
                     if open_jsx_component_register.peek() != Some(&JSXComponentType::Poll) {
                         open_jsx_component_register.push(JSXComponentType::Poll);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::Poll, block);
                 }
                 LineType::PollOpening => {
                     if open_jsx_component_register.peek() != Some(&JSXComponentType::PollOpening) {
                         open_jsx_component_register.push(JSXComponentType::PollOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::Poll, block);
                 }
                 LineType::VideoOpen => {
                     let current_open_jsx_component = open_jsx_component_register.peek();
@@ -1605,20 +3275,20 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     } else if current_open_jsx_component != Some(&JSXComponentType::Video) {
                         open_jsx_component_register.push(JSXComponentType::Video);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::Video, block);
                 }
                 LineType::VideoOpening => {
                     if open_jsx_component_register.peek() != Some(&JSXComponentType::VideoOpening) {
                         open_jsx_component_register.push(JSXComponentType::VideoOpening);
                     }
-                    tokens.push(line);
+                    add_jsx_component_line(&mut document, JSXComponentType::Video, block);
                 }
                 LineType::HTMLBlockLevelCommentOpen => {
                     if open_html_block_element_stack.peek() != Some(&HTMLBlockElementType::Comment)
                     {
                         open_html_block_element_stack.push(HTMLBlockElementType::Comment);
                     }
-                    tokens.push(line);
+                    add_html_block_line(&mut document, HTMLBlockElementType::Comment, block);
                 }
                 LineType::HTMLDescriptionListOpen => {
                     if open_html_block_element_stack.peek()
@@ -1626,131 +3296,145 @@ pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
                     {
                         open_html_block_element_stack.push(HTMLBlockElementType::DescriptionList);
                     }
-                    tokens.push(line);
+                    add_html_block_line(
+                        &mut document,
+                        HTMLBlockElementType::DescriptionList,
+                        block,
+                    );
                 }
                 LineType::HTMLDivBlockOpen => {
                     if open_html_block_element_stack.peek() != Some(&HTMLBlockElementType::Div) {
                         open_html_block_element_stack.push(HTMLBlockElementType::Div);
                     }
-                    tokens.push(line);
+                    add_html_block_line(&mut document, HTMLBlockElementType::Div, block);
                 }
                 LineType::HTMLFigureBlockOpen => {
                     if open_html_block_element_stack.peek() != Some(&HTMLBlockElementType::Figure) {
                         open_html_block_element_stack.push(HTMLBlockElementType::Figure);
                     }
-                    tokens.push(line);
-                }
-                LineType::HTMLTableHeadOpen => {
-                    if open_html_block_element_stack.peek()
-                        != Some(&HTMLBlockElementType::TableHead)
-                    {
-                        open_html_block_element_stack.push(HTMLBlockElementType::TableHead);
-                    }
-                    tokens.push(line);
+                    add_html_block_line(&mut document, HTMLBlockElementType::Figure, block);
                 }
-                LineType::HTMLTableBodyOpen => {
-                    if open_html_block_element_stack.peek()
-                        != Some(&HTMLBlockElementType::TableBody)
-                    {
-                        open_html_block_element_stack.pop();
-                        open_html_block_element_stack.push(HTMLBlockElementType::TableBody);
+                LineType::HTMLTableHeadOpen | LineType::HTMLTableBodyOpen => {
+                    let element = if line_type == LineType::HTMLTableHeadOpen {
+                        HTMLBlockElementType::TableHead
+                    } else {
+                        HTMLBlockElementType::TableBody
+                    };
+                    if open_html_block_element_stack.peek() != Some(&element) {
+                        if element == HTMLBlockElementType::TableBody {
+                            open_html_block_element_stack.pop();
+                        }
+                        open_html_block_element_stack.push(element);
                     }
-                    tokens.push(line);
-                }
-                _ => tokens.push(line),
-            },
-            None => {
-                while !open_lists.is_empty() {
-                    match open_lists.pop() {
-                        Some(ListType::Unordered) => tokens.push(String::from("</ul>")),
-                        Some(ListType::Ordered) => {
-                            tokens.push(String::from("</ol>"));
-                            open_markdown_block_stack.pop();
+                    match (document.peek_mut(), block) {
+                        (Some(Block::Table(open_table)), Block::Table(table)) => {
+                            open_table.append(table);
                         }
-                        None => {}
+                        (_, block) => document.open(block),
                     }
                 }
+                _ => document.push(block),
             }
+        }
+    }
+
+    close_paragraph(
+        &mut document,
+        &mut paragraph_lines,
+        &mut link_references,
+        file,
+        source,
+    )?;
+
+    if let Some((line_index, reference)) = link_references
+        .iter()
+        .find(|(_, reference)| !link_definitions.contains_key(&reference.label))
+    {
+        let line = source.lines().nth(*line_index).unwrap_or_default();
+        let error = LineError {
+            input: line.find(&reference.source).map_or(line, |offset| {
+                &line[offset..offset + reference.source.len()]
+            }),
+            kind: ParseErrorKind::UnresolvedLinkReference(reference.label.clone()),
         };
+        return Err(ParseError::from_line_error(
+            &error,
+            file,
+            source,
+            line,
+            line_index + 1,
+        ));
     }
-    let astro_frontmatter = form_astro_frontmatter(
-        &present_jsx_component_types,
-        &astro_frontmatter_markup,
-        slug,
-    );
-    if verbose {
-        for frontmatter_line in &astro_frontmatter {
-            println!("{frontmatter_line}");
-        }
-        for token in &tokens {
-            println!("{token}");
-        }
-        println! {"\n"};
+    let mut blocks = document.finish();
+    let mut footnotes = collect_footnotes(&mut blocks);
+    let headings = collect_headings(&mut blocks);
+    place_table_of_contents(&mut blocks, &headings);
+    for block in blocks.iter_mut().chain(
+        footnotes
+            .iter_mut()
+            .flat_map(|footnote| &mut footnote.children),
+    ) {
+        block.visit_inlines_mut(&mut |inlines| resolve_link_references(inlines, &link_definitions));
     }
 
-    let Ok(mut outfile) = File::create(output_path) else {
-        panic!(
-            "[ ERROR ] Was not able to create the output file: {:?}!",
-            output_path.as_ref().display().to_string()
-        )
+    Ok(Document {
+        blocks,
+        declarations,
+        footnotes,
+        frontmatter,
+        headings: headings.iter().map(document_heading).collect(),
+        image_assets: Vec::new(),
+    })
+}
+
+pub fn parse_mdx_file<P1: AsRef<Path>, P2: AsRef<Path>>(
+    input_path: &P1,
+    output_path: &P2,
+    images: ImageOutput,
+    footnotes: FootnoteOutput,
+    typography: &Typography,
+    callout_component: &str,
+) -> Result<AstroOutput, ParseError> {
+    let input_display_path = input_path.as_ref().display().to_string();
+    let source = fs::read_to_string(input_path).map_err(|source| ParseError::Io {
+        path: input_display_path.clone(),
+        source,
+    })?;
+    let options = ConvertOptions {
+        slug: slug_from_input_file_path(input_path)?.to_string(),
+        images,
+        footnotes,
+        typography: typography.clone(),
+        callout_component: callout_component.to_string(),
+    };
+    let output = convert_source(&source, &input_display_path, &options)?;
+
+    let output_error = |source| ParseError::Io {
+        path: output_path.as_ref().display().to_string(),
+        source,
     };
+    let mut outfile = File::create(output_path).map_err(output_error)?;
 
     // Experimental formatting currently disabled
     let format = false;
 
     if format {
-        let mut cursor = Cursor::new(Vec::new());
-        for line in &astro_frontmatter {
-            cursor
-                .write_all(line.as_bytes())
-                .expect("[ ERROR ] Intermediate Astro buffer should have access to enough memory for markup.");
-        }
-        for line in &tokens {
-            cursor.write_all(line.as_bytes()).expect(
-                "[ ERROR ] Intermediate Astro buffer should have access to enough memory for markup."
-                );
-        }
-
-        let mut buffer = Vec::new();
-        cursor.rewind().unwrap();
-        cursor.read_to_end(&mut buffer).unwrap();
-
         let options = FormatOptions::default();
-        let formatted = format_text(
-            std::str::from_utf8(&buffer)
-                .expect("[ ERROR ] Astro markup should not contain UTF-8 characters."),
-            Language::Astro,
-            &options,
-            |code, _| Ok(code.into()),
-        )
-        .unwrap_or_else(|_| {
-            panic!(
-            "[ ERROR ] Unformatted intermediate file `{}` should not contain syntactical errors.",
-            output_path.as_ref().display())
-        });
-        let _ = outfile.write_all(formatted.as_bytes());
+        let formatted = format_text(&output.to_string(), Language::Astro, &options, |code, _| {
+            Ok(code.into())
+        })
+        .map_err(|error| ParseError::Format {
+            path: output_path.as_ref().display().to_string(),
+            message: error.to_string(),
+        })?;
+        outfile
+            .write_all(formatted.as_bytes())
+            .map_err(output_error)?;
     } else {
-        for line in &astro_frontmatter {
-            outfile
-                .write_all(line.as_bytes())
-                .expect("[ ERROR ] Was not able to create the output file!");
-            outfile
-                .write_all(b"\n")
-                .expect("[ ERROR ] Was not able to create the output file!");
-        }
-        for line in &tokens {
-            outfile
-                .write_all(line.as_bytes())
-                .expect("[ ERROR ] Was not able to create the output file!");
-            outfile
-                .write_all(b"\n")
-                .expect("[ ERROR ] Was not able to create the output file!");
-        }
+        outfile
+            .write_all(output.to_string().as_bytes())
+            .map_err(output_error)?;
     }
 
-    let duration = start.elapsed();
-    let duration_milliseconds = duration.as_millis();
-    let duration_microseconds = duration.as_micros() - (duration_milliseconds * 1000);
-    let file_size = file.metadata().unwrap().len() / 1000;
-    println!("[ INFO ] Parsing complete ({file_size} KB) in {duration_milliseconds}.{duration_microseconds:0>3} ms.");
+    Ok(output)
 }
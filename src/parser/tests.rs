@@ -1,26 +1,104 @@
 use crate::parser::{
-    discard_leading_whitespace, escape_code, form_code_fragment_component_first_line,
-    form_code_span_line, form_fenced_code_block_first_line, form_html_anchor_element_line,
+    ast::{
+        Block, BlockQuote, CalloutType, CodeBlock, DocumentHeading, Footnote, FootnoteReference,
+        HTMLBlockElementType, Heading, HtmlBlock, Image, Inline, JsxComponent, Link,
+        LinkDefinition, LinkReference, List, ListItem, ListType, Table, TableAlign, TableRow,
+    },
+    convert_str, discard_leading_whitespace,
+    error::{LineError, ParseError, ParseErrorKind},
+    form_code_fragment_component_first_line, form_code_span_line,
+    form_fenced_code_block_first_line, form_html_anchor_element_line,
     form_html_block_level_comment_first_line, form_html_block_level_comment_last_line,
-    form_inline_wrap_text, form_ordered_list_line, form_table_body_last_line, form_table_body_row,
-    form_table_head_first_line, form_table_head_last_line, form_table_head_row,
-    form_table_header_row, format_heading, format_heading_widows,
-    format_inline_wrap_text_number_range, parse_closing_html_tag,
-    parse_fenced_code_block_first_line, parse_heading_text, parse_href_scheme,
-    parse_html_block_level_comment_last_line, parse_html_tag_attribute, parse_html_tag_attributes,
-    parse_html_tag_content, parse_inline_wrap_segment, parse_inline_wrap_text, parse_mdx_line,
+    form_inline_wrap_text, form_markdown_image_line, form_markdown_link_line,
+    form_ordered_list_line, form_table_body_last_line, form_table_body_row,
+    form_table_head_last_line, form_table_head_row, form_table_header_row,
+    form_unordered_list_line,
+    jsx::{CodeFence, JSXComponentType},
+    parse_backslash_escape, parse_callout_first_line, parse_closing_html_tag, parse_code_fence,
+    parse_code_span, parse_document, parse_fenced_code_block_first_line,
+    parse_fenced_code_block_last_line, parse_footnote_definition, parse_heading_attributes,
+    parse_heading_text, parse_href_scheme, parse_html_block_level_comment_last_line,
+    parse_html_tag_attribute, parse_html_tag_attributes, parse_html_tag_content,
+    parse_inline_wrap_text, parse_link_reference_definition, parse_mdx_line,
     parse_opening_html_tag, parse_opening_html_tag_end, parse_opening_html_tag_no_attributes,
     parse_opening_html_tag_start, parse_opening_html_tag_with_attributes, parse_ordered_list_text,
-    parse_self_closing_html_tag, parse_self_closing_html_tag_end, parse_table_cell,
-    parse_table_column_alignment, parse_table_header_row, parse_table_line,
-    parse_unordered_list_text, parse_up_to_inline_wrap_segment, parse_up_to_opening_html_tag,
-    remove_html_tags, segment_emphasis_line, segment_strong_emphasis_line, slugify_title,
-    HTMLTagType, LineType, TableAlign,
-};
-use nom::{
-    error::{Error, ErrorKind},
-    Err,
+    parse_self_closing_html_tag, parse_self_closing_html_tag_end, parse_setext_heading_underline,
+    parse_table_cell, parse_table_column_alignment, parse_table_header_row, parse_table_line,
+    parse_thematic_break, parse_unordered_list_text, parse_up_to_opening_html_tag,
+    remove_html_tags, slug_from_input_file_path, slugify_title, strip_block_quote_markers,
+    strip_heading_closing_sequence, ConvertOptions, FootnoteOutput, HTMLTagType, ImageOutput,
+    LineType, Locale, Typography, DEFAULT_CALLOUT_COMPONENT,
 };
+use nom::{error::ErrorKind, Err};
+
+#[test]
+pub fn test_convert_str() {
+    let mdx = "---
+title: Getting Started with NewTech
+---
+
+## What is NewTech?
+
+NewTech was **first** set up to solve the common problem.
+";
+    let options = ConvertOptions {
+        slug: String::from("getting-started-with-newtech"),
+        images: ImageOutput::PostImages,
+        footnotes: FootnoteOutput::Section,
+        typography: Typography::default(),
+        callout_component: String::from(DEFAULT_CALLOUT_COMPONENT),
+    };
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output.frontmatter.starts_with("---\n"));
+    assert!(output.frontmatter.ends_with("---\n\n"));
+    assert!(output
+        .frontmatter
+        .contains("const frontmatter = {\n  title: 'Getting Started with NewTech',\n} as const;"));
+    assert_eq!(
+        output.body,
+        "<h2 id=\"what-is-newtech\"><Heading client:visible id=\"what-is-newtech\" text=\"What is NewTech?\"/></h2>
+<p>NewTech was <strong>first</strong> set up to solve the common problem.</p>
+"
+    );
+    assert_eq!(
+        output.to_string(),
+        format!("{}{}", output.frontmatter, output.body)
+    );
+
+    // no frontmatter
+    let output = convert_str("Just a paragraph.", &options).unwrap();
+
+    // errors carry the line and column of the problem
+    let mdx = "## Feedback\n\n<section>\n";
+    match convert_str(mdx, &options) {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::UnrecognisedBlockElement(String::from("section"))
+            );
+            assert_eq!(error.line, 3);
+            assert_eq!(error.column, 1);
+        }
+        _ => panic!("Expected unrecognised block element error"),
+    }
+    assert_eq!(output.body, "<p>Just a paragraph.</p>\n");
+
+    // the Callout component import can be chosen
+    let mdx = "> [!TIP]\n> Use a changing bag.\n";
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output
+        .frontmatter
+        .contains("import Callout from '~components/Callout.svelte';"));
+    let options = ConvertOptions {
+        callout_component: String::from("@site/components/Admonition.astro"),
+        ..options
+    };
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output
+        .frontmatter
+        .contains("import Callout from '@site/components/Admonition.astro';"));
+    assert!(output.body.starts_with("<Callout type=\"tip\">"));
+}
 
 #[test]
 pub fn test_discard_leading_whitespace() {
@@ -31,15 +109,6 @@ pub fn test_discard_leading_whitespace() {
         );
 }
 
-#[test]
-pub fn test_escape_code() {
-    let mdx_line = "`${variable}`";
-    assert_eq!(
-        escape_code(mdx_line),
-        "\\u0060$\\u007Bvariable\\u007D\\u0060"
-    );
-}
-
 #[test]
 pub fn test_form_code_fragment_component_first_line() {
     let mdx_line = "<CodeFragment";
@@ -90,20 +159,12 @@ pub fn test_form_fenced_code_block_first_line() {
         Ok((
             "",
             (
-                String::from("<CodeFragment\n  client:visible\n  language=\"plaintext\"\n  highlightLines={`{5,7}`}\n  title=\".env\"\n  code={`"),
-                LineType::FencedCodeBlockOpen,
-                0
-            )
-        ))
-    );
-
-    let mdx_line = "```plaintext 2 {5,7} \".env\"";
-    assert_eq!(
-        form_fenced_code_block_first_line(mdx_line),
-        Ok((
-            "",
-            (
-                String::from("<CodeFragment\n  client:visible\n  language=\"plaintext\"\n  firstLine={2}\n  highlightLines={`{5,7}`}\n  title=\".env\"\n  code={`"),
+                Block::CodeBlock(CodeBlock {
+                    language: Some(String::from("plaintext")),
+                    highlight_line_numbers: Some(String::from("{5,7}")),
+                    title: Some(String::from(".env")),
+                    ..Default::default()
+                }),
                 LineType::FencedCodeBlockOpen,
                 0
             )
@@ -116,7 +177,14 @@ pub fn test_form_fenced_code_block_first_line() {
         Ok((
             "",
             (
-                String::from("<CodeFragment\n  client:visible\n  language=\"plaintext\"\n  firstLine={2}\n  highlightLines={`{5,7}`}\n  title=\".env\"\n  collapse\n  code={`"),
+                Block::CodeBlock(CodeBlock {
+                    language: Some(String::from("plaintext")),
+                    first_line_number: Some(String::from("2")),
+                    highlight_line_numbers: Some(String::from("{5,7}")),
+                    title: Some(String::from(".env")),
+                    collapse: true,
+                    ..Default::default()
+                }),
                 LineType::FencedCodeBlockOpen,
                 0
             )
@@ -126,59 +194,345 @@ pub fn test_form_fenced_code_block_first_line() {
 
 #[test]
 pub fn test_form_html_anchor_element_line() {
-    // adds rel and target attributes for external sites when they are not already there
     let mdx_line = "<a href=\"https://www.example.com\">site</a>.";
     assert_eq!(
-            form_html_anchor_element_line(mdx_line),
-            Ok((
-                ".",
-                String::from(
-                    "<a href=\"https://www.example.com\" target=\"_blank\" rel=\"nofollow noopener noreferrer\">site&nbsp;<LinkIcon /></a>"
-                )
-            ))
-        );
+        form_html_anchor_element_line(mdx_line),
+        Ok((
+            ".",
+            vec![Inline::Link(Link {
+                href: String::from("https://www.example.com"),
+                attributes: String::from("href=\"https://www.example.com\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("site"))]
+            })]
+        ))
+    );
 
-    // does not add rel and target attributes to non external sites
-    let mdx_line = "<a href=\"/home/contact-us\">site</a>.";
+    let mdx_line = "Go to <a href=\"www.example.com\" rel=\"me\">site</a> to learn more.";
     assert_eq!(
         form_html_anchor_element_line(mdx_line),
-        Ok((".", String::from("<a href=\"/home/contact-us\">site</a>")))
+        Ok((
+            " to learn more.",
+            vec![
+                Inline::Text(String::from("Go to ")),
+                Inline::Link(Link {
+                    href: String::from("www.example.com"),
+                    attributes: String::from("href=\"www.example.com\" rel=\"me\""),
+                    has_rel: true,
+                    has_target: false,
+                    content: vec![Inline::Text(String::from("site"))]
+                })
+            ]
+        ))
     );
+}
 
-    let mdx_line = "Go to <a href=\"www.example.com\">site</a> to learn more.";
+#[test]
+pub fn test_form_html_anchor_element_line_missing_href() {
+    // fails, pointing at the opening tag, if href attribute is not present
+    let mdx_line = "See <a to=\"https://www.example.com\">site</a>.";
     assert_eq!(
         form_html_anchor_element_line(mdx_line),
+        Err(LineError::failure(
+            "<a to=\"https://www.example.com\">",
+            ParseErrorKind::MissingHref
+        ))
+    );
+}
+
+#[test]
+pub fn test_form_markdown_link_line() {
+    let mdx_line =
+        "[**latest** `console.log()` example](https://www.example.com \"Latest\") to learn more.";
+    assert_eq!(
+        form_markdown_link_line(mdx_line),
         Ok((
             " to learn more.",
-            String::from("Go to <a href=\"www.example.com\">site</a>")
+            vec![Inline::Link(Link {
+                href: String::from("https://www.example.com"),
+                attributes: String::from("href=\"https://www.example.com\" title=\"Latest\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![
+                    Inline::Strong(vec![Inline::Text(String::from("latest"))]),
+                    Inline::Text(String::from(" ")),
+                    Inline::Code(String::from("console.log()")),
+                    Inline::Text(String::from(" example"))
+                ]
+            })]
+        ))
+    );
+
+    // parentheses in the destination must be balanced, unless it is wrapped in angle brackets
+    let mdx_line = "[Rust](https://en.wikipedia.org/wiki/Rust_(programming_language)).";
+    assert_eq!(
+        form_markdown_link_line(mdx_line),
+        Ok((
+            ".",
+            vec![Inline::Link(Link {
+                href: String::from("https://en.wikipedia.org/wiki/Rust_(programming_language)"),
+                attributes: String::from(
+                    "href=\"https://en.wikipedia.org/wiki/Rust_(programming_language)\""
+                ),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("Rust"))]
+            })]
         ))
     );
+
+    let mdx_line = "[contact page](</contact us> 'Say \"hi\"')";
+    assert_eq!(
+        form_markdown_link_line(mdx_line),
+        Ok((
+            "",
+            vec![Inline::Link(Link {
+                href: String::from("/contact us"),
+                attributes: String::from("href=\"/contact us\" title=\"Say &quot;hi&quot;\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("contact page"))]
+            })]
+        ))
+    );
+
+    // escaped delimiters do not close the title
+    let mdx_line = r#"[x](https://a.com "He said \"hi\"") and more"#;
+    assert_eq!(
+        form_markdown_link_line(mdx_line),
+        Ok((
+            " and more",
+            vec![Inline::Link(Link {
+                href: String::from("https://a.com"),
+                attributes: String::from("href=\"https://a.com\" title=\"He said &quot;hi&quot;\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("x"))]
+            })]
+        ))
+    );
+    let mdx_line = r"[y](/y (a \) b))";
+    assert_eq!(
+        form_markdown_link_line(mdx_line),
+        Ok((
+            "",
+            vec![Inline::Link(Link {
+                href: String::from("/y"),
+                attributes: String::from("href=\"/y\" title=\"a ) b\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("y"))]
+            })]
+        ))
+    );
+
+    let mdx_line = "[not a link] here";
+    assert!(form_markdown_link_line(mdx_line).is_err());
 }
 
 #[test]
-#[should_panic(expected = "[ ERROR ] Anchor tag missing href")]
-pub fn test_form_html_anchor_element_line_panic() {
-    // Panics if href attribute is not present
-    let mdx_line = "<a to=\"https://www.example.com\">site</a>.";
+pub fn test_form_markdown_image_line() {
+    let mdx_line = "![Agfa Isolette camera](./camera.jpg \"Agfa Isolette\") folded.";
     assert_eq!(
-            form_html_anchor_element_line(mdx_line),
-            Ok((
-                "site</a>.",
-                String::from(
-                    "<a href=\"https://www.example.com\" target=\"_blank\" rel=\"nofollow noopener noreferrer\">"
-                )
-            ))
-        );
+        form_markdown_image_line(mdx_line),
+        Ok((
+            " folded.",
+            vec![Inline::Image(Image {
+                alt: String::from("Agfa Isolette camera"),
+                src: String::from("./camera.jpg"),
+                title: Some(String::from("Agfa Isolette")),
+                local: None
+            })]
+        ))
+    );
+
+    let mdx_line = r"![Camera](./camera.jpg 'Agfa\'s Isolette')";
+    assert_eq!(
+        form_markdown_image_line(mdx_line),
+        Ok((
+            "",
+            vec![Inline::Image(Image {
+                alt: String::from("Camera"),
+                src: String::from("./camera.jpg"),
+                title: Some(String::from("Agfa's Isolette")),
+                local: None
+            })]
+        ))
+    );
+
+    // fails, pointing at the image, if there is no alt text
+    let mdx_line = "![](./camera.jpg) folded.";
+    assert_eq!(
+        form_markdown_image_line(mdx_line),
+        Err(LineError::failure(
+            "![](./camera.jpg)",
+            ParseErrorKind::MissingAltText
+        ))
+    );
+}
+
+#[test]
+pub fn test_convert_str_images() {
+    let mdx = "![Agfa Isolette camera](./camera.jpg)
+
+Compare ![Mamiya 6](https://www.example.com/mamiya.jpg) with ![Agfa](./camera.jpg).
+";
+    let mut options = ConvertOptions {
+        slug: String::from("medium-format-cameras"),
+        images: ImageOutput::PostImages,
+        footnotes: FootnoteOutput::Section,
+        typography: Typography::default(),
+        callout_component: String::from(DEFAULT_CALLOUT_COMPONENT),
+    };
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output
+        .frontmatter
+        .contains("import Image from '~components/BlogPost/Image.svelte';"));
+    assert!(output
+        .frontmatter
+        .contains("await getEntry('post-images', slug);"));
+    assert_eq!(
+        output.body,
+        "<Image index={0} alt=\"Agfa Isolette camera\" />
+<p>Compare <img src=\"https://www.example.com/mamiya.jpg\" alt=\"Mamiya 6\" loading=\"lazy\" decoding=\"async\" /> with <Image index={0} alt=\"Agfa\" />.</p>
+"
+    );
+
+    // Markdown images are numbered after hand-written `Image` components
+    let mdx = "<Image index={2} />
+
+![Agfa Isolette camera](./camera.jpg \"Folded\")
+";
+    let output = convert_str(mdx, &options).unwrap();
+    assert_eq!(
+        output.body,
+        "<Image index={2} />
+<Image index={3} alt=\"Agfa Isolette camera\" title=\"Folded\" />
+"
+    );
+
+    let mdx = "![Agfa Isolette camera](./camera.jpg)\n";
+    options.images = ImageOutput::AstroAssets;
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output.frontmatter.contains(
+        "import { Image as AstroImage } from 'astro:assets';\nimport image0 from './camera.jpg';\n"
+    ));
+    assert!(!output.frontmatter.contains("post-images"));
+    assert!(output
+        .body
+        .starts_with("<AstroImage src={image0} alt=\"Agfa Isolette camera\" />\n"));
+
+    let mdx = "## Cameras\n\n![ ](./camera.jpg)\n";
+    match convert_str(mdx, &options) {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::MissingAltText);
+            assert_eq!(error.line, 3);
+        }
+        _ => panic!("Expected missing alt text error"),
+    }
+
+    // remote images need alt text too, while decorative images are written as HTML
+    let mdx = "![](https://example.com/rule.png)\n";
+    assert!(matches!(
+        convert_str(mdx, &options),
+        Err(ParseError::Source(error)) if error.kind == ParseErrorKind::MissingAltText
+    ));
+    let mdx = "<img src=\"https://example.com/rule.png\" alt=\"\" />\n";
+    let output = convert_str(mdx, &options).unwrap();
+    assert_eq!(
+        output.body,
+        "<p><img src=\"https://example.com/rule.png\" alt=\"\" /></p>\n"
+    );
 }
 
 #[test]
 pub fn test_form_code_span_line() {
     let mdx_line = "NewTech `console.log(\"made it here\")` first set up to solve the common problem coming up for identifiers in computer science.";
     assert_eq!(
-            form_code_span_line(mdx_line),
-            // Ok((" first set up to solve the common problem coming up for identifiers in computer science.",String::from("NewTech <code>console.log(\"made it here\")</code>")))
-            Ok((" first set up to solve the common problem coming up for identifiers in computer science.",String::from("NewTech <InlineCodeFragment code={`console.log(\"made it here\")`} />")))
-        );
+        form_code_span_line(mdx_line),
+        Ok((
+            " first set up to solve the common problem coming up for identifiers in computer science.",
+            vec![
+                Inline::Text(String::from("NewTech ")),
+                Inline::Code(String::from("console.log(\"made it here\")"))
+            ]
+        ))
+    );
+}
+
+#[test]
+pub fn test_parse_code_span() {
+    assert_eq!(parse_code_span("`code` text"), Ok((" text", "code")));
+    assert_eq!(parse_code_span("`` a`b `` text"), Ok((" text", "a`b")));
+    assert_eq!(parse_code_span("`` `a` ``"), Ok(("", "`a`")));
+    assert_eq!(parse_code_span("`  `"), Ok(("", "  ")));
+    assert_eq!(parse_code_span("```a``b```"), Ok(("", "a``b")));
+    assert_eq!(
+        parse_code_span("``a`"),
+        Err(Err::Error(LineError::new("``a`", ErrorKind::TakeUntil)))
+    );
+}
+
+#[test]
+pub fn test_parse_backslash_escape() {
+    assert_eq!(
+        parse_backslash_escape("\\*text"),
+        Ok(("text", String::from("*")))
+    );
+    assert_eq!(
+        parse_backslash_escape("\\<div>"),
+        Ok(("div>", String::from("&lt;")))
+    );
+    assert_eq!(
+        parse_backslash_escape("\\{value}"),
+        Ok(("value}", String::from("&#123;")))
+    );
+    assert!(parse_backslash_escape("\\a").is_err());
+}
+
+#[test]
+pub fn test_parse_inline_escapes_and_code_spans() {
+    let mdx_line = "Use \\*literal\\* asterisks, \\`ticks\\` and `` a`b `` or ``unclosed` code`.";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("Use *literal* asterisks, `ticks` and ")),
+                Inline::Code(String::from("a`b")),
+                Inline::Text(String::from(" or ``unclosed")),
+                Inline::Code(String::from(" code")),
+                Inline::Text(String::from("."))
+            ]
+        ))
+    );
+
+    let mdx_line = "\\[not a link\\](https://example.com) and [a \\] bracket](/a\\)b)";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("[not a link](")),
+                Inline::Link(Link {
+                    href: String::from("https://example.com"),
+                    attributes: String::from("href=\"https://example.com\""),
+                    has_rel: false,
+                    has_target: false,
+                    content: vec![Inline::Text(String::from("https://example.com"))],
+                }),
+                Inline::Text(String::from(") and ")),
+                Inline::Link(Link {
+                    href: String::from("/a)b"),
+                    attributes: String::from("href=\"/a)b\""),
+                    has_rel: false,
+                    has_target: false,
+                    content: vec![Inline::Text(String::from("a ] bracket"))],
+                }),
+            ]
+        ))
+    );
 }
 
 #[test]
@@ -189,7 +543,7 @@ pub fn test_form_html_block_level_comment_first_line() {
         Ok((
             "",
             (
-                String::from("<!-- this should do so and so"),
+                Block::Markup(String::from("<!-- this should do so and so")),
                 LineType::HTMLBlockLevelCommentOpen,
                 0
             )
@@ -205,7 +559,7 @@ pub fn test_form_html_block_level_comment_last_line() {
         Ok((
             "",
             (
-                String::from("this comment is not over yet"),
+                Block::Markup(String::from("this comment is not over yet")),
                 LineType::HTMLBlockLevelCommentOpen,
                 0
             )
@@ -218,7 +572,7 @@ pub fn test_form_html_block_level_comment_last_line() {
         Ok((
             "",
             (
-                String::from("just saying! -->"),
+                Block::Markup(String::from("just saying! -->")),
                 LineType::HTMLBlockLevelComment,
                 0
             )
@@ -231,7 +585,7 @@ pub fn test_form_html_block_level_comment_last_line() {
         Ok((
             "",
             (
-                String::from("just saying! -->  <p>The problem with"),
+                Block::Markup(String::from("just saying! -->  <p>The problem with")),
                 LineType::HTMLBlockLevelComment,
                 0
             )
@@ -241,48 +595,59 @@ pub fn test_form_html_block_level_comment_last_line() {
 
 #[test]
 pub fn test_form_inline_wrap_text() {
-    // does not create paragraph tags for empty line
-    let mdx_line = "";
-    assert_eq!(
-        form_inline_wrap_text(mdx_line),
-        Ok(("", (String::new(), LineType::Paragraph, 0)))
-    );
-
-    // adds paragraph tags for non-empty line
     let mdx_line = "NewTech was first set up to solve the common problem coming up for identifiers in computer science.";
     assert_eq!(
         form_inline_wrap_text(mdx_line),
-        Ok(("", (String::from("<p>NewTech was first set up to solve the common problem coming up for identifiers in computer science.</p>"), LineType::Paragraph, 0)))
+        Ok((
+            "",
+            (
+                Block::Paragraph(vec![Inline::Text(String::from(mdx_line))]),
+                LineType::Paragraph,
+                0
+            )
+        ))
     );
 
-    // add paragraph containing inline code fragment and emphasised text
+    // paragraph containing inline code fragment and emphasised text
     let mdx_line = "To me `E=mc^2` rather than `F=ma` is **the** most important equation.";
-    assert_eq!(form_inline_wrap_text(mdx_line), Ok(("", (String::from("<p>To me <InlineCodeFragment code={`E=mc^2`} /> rather than <InlineCodeFragment code={`F=ma`} /> is <strong>the</strong> most important equation.</p>"), LineType::Paragraph, 0))) );
-}
-
-#[test]
-pub fn test_form_ordered_list_line() {
-    // does not create paragraph tags for empty line
-    let mdx_line = "1. first things first";
     assert_eq!(
-        form_ordered_list_line(mdx_line),
+        form_inline_wrap_text(mdx_line),
         Ok((
             "",
             (
-                String::from("  <li>first things first"),
-                LineType::OrderedListItemOpen,
+                Block::Paragraph(vec![
+                    Inline::Text(String::from("To me ")),
+                    Inline::Code(String::from("E=mc^2")),
+                    Inline::Text(String::from(" rather than ")),
+                    Inline::Code(String::from("F=ma")),
+                    Inline::Text(String::from(" is ")),
+                    Inline::Strong(vec![Inline::Text(String::from("the"))]),
+                    Inline::Text(String::from(" most important equation."))
+                ]),
+                LineType::Paragraph,
                 0
             )
         ))
     );
+}
 
-    let mdx_line = "1. first things **before** second things";
+#[test]
+pub fn test_form_ordered_list_line() {
+    let mdx_line = "3. first things **before** second things";
     assert_eq!(
         form_ordered_list_line(mdx_line),
         Ok((
-            "",
+            "first things **before** second things",
             (
-                String::from("  <li>first things <strong>before</strong> second things"),
+                Block::List(List {
+                    list_type: ListType::Ordered,
+                    start: Some(String::from("3")),
+                    loose: false,
+                    items: vec![ListItem {
+                        checked: None,
+                        children: Vec::new()
+                    }]
+                }),
                 LineType::OrderedListItemOpen,
                 0
             )
@@ -298,13 +663,16 @@ pub fn test_form_table_body_last_line() {
         Ok((
             "",
             (
-                String::from(
-                    "    <tr>
-      <td>1 January</td>
-      <td>Central London</td>
-      <td>Sunny</td>
-    </tr>"
-                ),
+                Block::Table(Table {
+                    body: vec![TableRow {
+                        cells: vec![
+                            vec![Inline::Text(String::from("1 January"))],
+                            vec![Inline::Text(String::from("Central London"))],
+                            vec![Inline::Text(String::from("Sunny"))]
+                        ]
+                    }],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableBodyOpen,
                 0
             )
@@ -316,11 +684,7 @@ pub fn test_form_table_body_last_line() {
         form_table_body_last_line(mdx_line),
         Ok((
             "",
-            (
-                String::from("  </tbody>\n</table>"),
-                LineType::HTMLTableBody,
-                0
-            )
+            (Block::Table(Table::default()), LineType::HTMLTableBody, 0)
         ))
     );
 }
@@ -333,13 +697,16 @@ pub fn test_form_table_body_row() {
         Ok((
             "",
             (
-                String::from(
-                    "    <tr>
-      <td>1 January</td>
-      <td>Central London</td>
-      <td>Sunny</td>
-    </tr>"
-                ),
+                Block::Table(Table {
+                    body: vec![TableRow {
+                        cells: vec![
+                            vec![Inline::Text(String::from("1 January"))],
+                            vec![Inline::Text(String::from("Central London"))],
+                            vec![Inline::Text(String::from("Sunny"))]
+                        ]
+                    }],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableBodyOpen,
                 0
             )
@@ -348,26 +715,28 @@ pub fn test_form_table_body_row() {
 }
 
 #[test]
-pub fn test_form_table_head_first_line() {
-    let mdx_line = "| 1 January | Central London | Sunny |";
-    assert_eq!(
-        form_table_head_first_line(mdx_line),
-        Ok((
-            "",
-            (
-                String::from(
-                    "<table>
-  <thead>
-    <tr>
-      <th scope=\"col\">1 January </th>
-      <th scope=\"col\">Central London </th>
-      <th scope=\"col\">Sunny </th>
-    </tr>"
-                ),
-                LineType::HTMLTableHeadOpen,
-                0
-            )
-        ))
+pub fn test_form_table_body_row_inline_markup() {
+    let mdx_line = "| `a \\| b` | **Bold** \\| plain | [Docs](https://example.com) |";
+    let (_, (block, _, _)) = form_table_body_row(mdx_line).unwrap();
+    let Block::Table(Table { body, .. }) = block else {
+        panic!("expected a table");
+    };
+    assert_eq!(
+        body[0].cells,
+        vec![
+            vec![Inline::Code(String::from("a | b"))],
+            vec![
+                Inline::Strong(vec![Inline::Text(String::from("Bold"))]),
+                Inline::Text(String::from(" | plain"))
+            ],
+            vec![Inline::Link(Link {
+                href: String::from("https://example.com"),
+                attributes: String::from("href=\"https://example.com\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("Docs"))]
+            })]
+        ]
     );
 }
 
@@ -379,7 +748,14 @@ pub fn test_form_table_head_last_line() {
         Ok((
             "",
             (
-                String::from("  </thead>\n  <tbody>"),
+                Block::Table(Table {
+                    alignments: vec![
+                        Some(TableAlign::Left),
+                        Some(TableAlign::Centre),
+                        Some(TableAlign::Right)
+                    ],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableBodyOpen,
                 0
             )
@@ -392,13 +768,16 @@ pub fn test_form_table_head_last_line() {
         Ok((
             "",
             (
-                String::from(
-                    "    <tr>
-      <th scope=\"col\">1 January </th>
-      <th scope=\"col\">Central London </th>
-      <th scope=\"col\">Sunny </th>
-    </tr>"
-                ),
+                Block::Table(Table {
+                    head: vec![TableRow {
+                        cells: vec![
+                            vec![Inline::Text(String::from("1 January"))],
+                            vec![Inline::Text(String::from("Central London"))],
+                            vec![Inline::Text(String::from("Sunny"))]
+                        ]
+                    }],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableHeadOpen,
                 0
             )
@@ -414,13 +793,16 @@ pub fn test_form_table_head_row() {
         Ok((
             "",
             (
-                String::from(
-                    "    <tr>
-      <th scope=\"col\">1 January </th>
-      <th scope=\"col\">Central London </th>
-      <th scope=\"col\">Sunny </th>
-    </tr>"
-                ),
+                Block::Table(Table {
+                    head: vec![TableRow {
+                        cells: vec![
+                            vec![Inline::Text(String::from("1 January"))],
+                            vec![Inline::Text(String::from("Central London"))],
+                            vec![Inline::Text(String::from("Sunny"))]
+                        ]
+                    }],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableHeadOpen,
                 0
             )
@@ -436,7 +818,14 @@ pub fn test_form_table_header_row() {
         Ok((
             "",
             (
-                String::from("  </thead>\n  <tbody>"),
+                Block::Table(Table {
+                    alignments: vec![
+                        Some(TableAlign::Left),
+                        Some(TableAlign::Centre),
+                        Some(TableAlign::Right)
+                    ],
+                    ..Default::default()
+                }),
                 LineType::HTMLTableBodyOpen,
                 0
             )
@@ -445,79 +834,1089 @@ pub fn test_form_table_header_row() {
 }
 
 #[test]
-pub fn test_format_heading() {
-    let title = "Introducing the new cat";
-    assert_eq!(format_heading(title), "Introducing the new cat");
+pub fn test_parse_document() {
+    let mdx = "- Agfa Isolette
+- Mamiya 6
+  - 75 mm lens
+
+<div>
+1. Load the tank
+</div>
+";
+    let document = parse_document(mdx, "<input>").unwrap();
+    let item = |text: &str| ListItem {
+        checked: None,
+        children: vec![Block::Paragraph(vec![Inline::Text(String::from(text))])],
+    };
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![
+                    item("Agfa Isolette"),
+                    ListItem {
+                        checked: None,
+                        children: vec![
+                            Block::Paragraph(vec![Inline::Text(String::from("Mamiya 6"))]),
+                            Block::List(List {
+                                list_type: ListType::Unordered,
+                                start: None,
+                                loose: false,
+                                items: vec![item("75 mm lens")]
+                            })
+                        ]
+                    }
+                ]
+            }),
+            Block::HtmlBlock(HtmlBlock {
+                element: HTMLBlockElementType::Div,
+                children: vec![
+                    Block::Markup(String::from("<div>")),
+                    Block::List(List {
+                        list_type: ListType::Ordered,
+                        start: Some(String::from("1")),
+                        loose: false,
+                        items: vec![item("Load the tank")]
+                    }),
+                    Block::Markup(String::from("</div>"))
+                ]
+            })
+        ]
+    );
+}
+
+#[test]
+pub fn test_parse_document_tables() {
+    let mdx = "Date | Weather
+--- | :---:
+1 January | Sunny | Windy
+2 January
+# Forecast
+
+Price | 3 | 4 are not a table
+";
+    let text = |value: &str| vec![Inline::Text(String::from(value))];
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Table(Table {
+                alignments: vec![None, Some(TableAlign::Centre)],
+                head: vec![TableRow {
+                    cells: vec![text("Date"), text("Weather")]
+                }],
+                body: vec![
+                    TableRow {
+                        cells: vec![text("1 January"), text("Sunny")]
+                    },
+                    TableRow {
+                        cells: vec![text("2 January"), Vec::new()]
+                    }
+                ]
+            }),
+            Block::Heading(Heading {
+                level: 1,
+                id: String::from("forecast"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: text("Forecast")
+            }),
+            Block::Paragraph(text("Price | 3 | 4 are not a table"))
+        ]
+    );
+}
 
-    let title = "Introducing the zoo's new elephant";
-    assert_eq!(
-        format_heading(title),
-        "Introducing the zoo\\u2019s new elephant"
+#[test]
+pub fn test_parse_document_paragraphs() {
+    let mdx = "NewTech was **first set
+up** to solve the `common
+problem`.  
+It's easy\\
+  to learn.
+
+- Agfa Isolette
+folding camera
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Paragraph(vec![
+                text("NewTech was "),
+                Inline::Strong(vec![text("first set"), Inline::SoftBreak, text("up")]),
+                text(" to solve the "),
+                Inline::Code(String::from("common problem")),
+                text("."),
+                Inline::LineBreak,
+                text("It's easy"),
+                Inline::LineBreak,
+                text("to learn.")
+            ]),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![ListItem {
+                    checked: None,
+                    children: vec![Block::Paragraph(vec![
+                        text("Agfa Isolette"),
+                        Inline::SoftBreak,
+                        text("folding camera")
+                    ])]
+                }]
+            })
+        ]
+    );
+
+    // errors point at the line within the paragraph
+    let mdx = "## Feedback\n\nPlease\n  go to <a to=\"/contact\">our\nsite</a>.\n";
+    match parse_document(mdx, "<input>") {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(error.kind, ParseErrorKind::MissingHref);
+            assert_eq!(error.line, 4);
+            assert_eq!(error.column, 9);
+            assert_eq!(error.span().len(), 17);
+        }
+        _ => panic!("Expected missing href error"),
+    }
+}
+
+#[test]
+pub fn test_parse_document_block_quotes() {
+    let mdx = "> Load the film
+in the dark.
+>
+> - Agfa Isolette
+>
+> > Nested quote
+>
+> ```shell
+> > not a quote
+> ```
+
+> [!TIP]
+> Use a changing bag.
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::BlockQuote(BlockQuote {
+                callout: None,
+                children: vec![
+                    Block::Paragraph(vec![
+                        text("Load the film"),
+                        Inline::SoftBreak,
+                        text("in the dark.")
+                    ]),
+                    Block::List(List {
+                        list_type: ListType::Unordered,
+                        start: None,
+                        loose: false,
+                        items: vec![ListItem {
+                            checked: None,
+                            children: vec![Block::Paragraph(vec![text("Agfa Isolette")])]
+                        }]
+                    }),
+                    Block::BlockQuote(BlockQuote {
+                        callout: None,
+                        children: vec![Block::Paragraph(vec![text("Nested quote")])]
+                    }),
+                    Block::CodeBlock(CodeBlock {
+                        language: Some(String::from("shell")),
+                        lines: vec![String::from("> not a quote")],
+                        ..Default::default()
+                    })
+                ]
+            }),
+            Block::BlockQuote(BlockQuote {
+                callout: Some(CalloutType::Tip),
+                children: vec![Block::Paragraph(vec![text("Use a changing bag.")])]
+            })
+        ]
+    );
+
+    // a callout ends an open indented code block or list
+    let mdx = "    code
+> [!NOTE]
+> Noted.
+- Item
+> [!TIP]
+";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::CodeBlock(CodeBlock {
+                lines: vec![String::from("code")],
+                ..Default::default()
+            }),
+            Block::BlockQuote(BlockQuote {
+                callout: Some(CalloutType::Note),
+                children: vec![Block::Paragraph(vec![text("Noted.")])]
+            }),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![ListItem {
+                    checked: None,
+                    children: vec![Block::Paragraph(vec![text("Item")])]
+                }]
+            }),
+            Block::BlockQuote(BlockQuote {
+                callout: Some(CalloutType::Tip),
+                children: Vec::new()
+            })
+        ]
     );
+}
 
-    let title = "\"Introducing\" the zoo's new elephant";
-    assert_eq!(
-        format_heading(title),
-        "\\u201cIntroducing\\u201d the zoo\\u2019s new elephant"
+#[test]
+pub fn test_parse_document_fenced_code_blocks() {
+    let mdx = "````markdown
+```rust
+let x = 1;
+```
+````
+
+~~~
+```
+~~~~
+
+  ```shell
+  ls
+    pwd
+ cd
+  ```
+";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::CodeBlock(CodeBlock {
+                language: Some(String::from("markdown")),
+                lines: vec![
+                    String::from("```rust"),
+                    String::from("let x = 1;"),
+                    String::from("```")
+                ],
+                ..Default::default()
+            }),
+            Block::CodeBlock(CodeBlock {
+                lines: vec![String::from("```")],
+                ..Default::default()
+            }),
+            Block::CodeBlock(CodeBlock {
+                language: Some(String::from("shell")),
+                lines: vec![
+                    String::from("ls"),
+                    String::from("  pwd"),
+                    String::from("cd")
+                ],
+                ..Default::default()
+            })
+        ]
     );
+}
 
-    let title = "It's not Right, but it's OK";
-    assert_eq!(
-        format_heading(title),
-        "It\\u2019s not Right, but it\\u2019s OK"
+#[test]
+pub fn test_parse_document_thematic_breaks() {
+    let mdx = "Rangefinders
+***
+- Rolleiflex
+* * *
+- Mamiya 6
+- - -
+Setext
+---
+> Quote
+___
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let list = |value: &str| {
+        Block::List(List {
+            list_type: ListType::Unordered,
+            start: None,
+            loose: false,
+            items: vec![ListItem {
+                checked: None,
+                children: vec![Block::Paragraph(vec![text(value)])],
+            }],
+        })
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Paragraph(vec![text("Rangefinders")]),
+            Block::ThematicBreak,
+            list("Rolleiflex"),
+            Block::ThematicBreak,
+            list("Mamiya 6"),
+            Block::ThematicBreak,
+            Block::Heading(Heading {
+                level: 2,
+                id: String::from("setext"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![text("Setext")]
+            }),
+            Block::BlockQuote(BlockQuote {
+                callout: None,
+                children: vec![Block::Paragraph(vec![text("Quote")])]
+            }),
+            Block::ThematicBreak,
+        ]
+    );
+
+    // without a closing delimiter, an opening `---` is not frontmatter
+    let mdx = "---\nTwin lens reflex\n";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(document.frontmatter, Vec::new());
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::ThematicBreak,
+            Block::Paragraph(vec![text("Twin lens reflex")])
+        ]
     );
 }
 
 #[test]
-pub fn test_format_heading_widows() {
-    let title = "Introducing the new cat";
-    assert_eq!(
-        format_heading_widows(title),
-        "Introducing the new\\u00a0cat"
+pub fn test_parse_document_heading_ids() {
+    let mdx = "## Feedback
+
+## Feedback {#feedback-form .wide}
+
+Feedback
+--------
+
+## Feedback-1
+
+## Object {#id} ##
+
+## Closed ##    {#closed}
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let heading = |id: &str, custom_id: bool, classes: Vec<String>, content: &str| {
+        Block::Heading(Heading {
+            level: 2,
+            id: String::from(id),
+            custom_id,
+            classes,
+            content: vec![text(content)],
+        })
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            heading("feedback", false, Vec::new(), "Feedback"),
+            heading(
+                "feedback-form",
+                true,
+                vec![String::from("wide")],
+                "Feedback"
+            ),
+            heading("feedback-1", false, Vec::new(), "Feedback"),
+            heading("feedback-1-1", false, Vec::new(), "Feedback-1"),
+            heading("id", true, Vec::new(), "Object"),
+            heading("closed", true, Vec::new(), "Closed"),
+        ]
+    );
+
+    // custom ids are kept as written, with slugs from titles taking a suffix instead
+    let document = parse_document("# Intro\n\n## Later {#intro}\n", "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Heading(Heading {
+                level: 1,
+                id: String::from("intro-1"),
+                custom_id: false,
+                classes: Vec::new(),
+                content: vec![text("Intro")]
+            }),
+            heading("intro", true, Vec::new(), "Later"),
+        ]
     );
+}
 
-    let title = "Introducing the zoo's new elephant";
+#[test]
+pub fn test_parse_document_table_of_contents() {
+    let mdx = "<TableOfContents />
+
+## Installing
+
+### Using `cargo`
+
+## Feedback
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let link = |href: &str, content: Vec<Inline>| {
+        Block::Paragraph(vec![Inline::Link(Link {
+            href: String::from(href),
+            attributes: format!("href=\"{href}\""),
+            has_rel: false,
+            has_target: false,
+            content,
+        })])
+    };
+    let list = |items: Vec<ListItem>| {
+        Block::List(List {
+            list_type: ListType::Unordered,
+            start: None,
+            loose: false,
+            items,
+        })
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.headings,
+        vec![
+            DocumentHeading {
+                depth: 2,
+                slug: String::from("installing"),
+                text: String::from("Installing")
+            },
+            DocumentHeading {
+                depth: 3,
+                slug: String::from("using-cargo"),
+                text: String::from("Using cargo")
+            },
+            DocumentHeading {
+                depth: 2,
+                slug: String::from("feedback"),
+                text: String::from("Feedback")
+            },
+        ]
+    );
+    assert_eq!(
+        document.blocks[0],
+        Block::JsxComponent(JsxComponent {
+            component: JSXComponentType::TableOfContents,
+            children: vec![list(vec![
+                ListItem {
+                    checked: None,
+                    children: vec![
+                        link("#installing", vec![text("Installing")]),
+                        list(vec![ListItem {
+                            checked: None,
+                            children: vec![link(
+                                "#using-cargo",
+                                vec![text("Using "), Inline::Code(String::from("cargo"))]
+                            )]
+                        }])
+                    ]
+                },
+                ListItem {
+                    checked: None,
+                    children: vec![link("#feedback", vec![text("Feedback")])]
+                }
+            ])]
+        })
+    );
+
+    let options = ConvertOptions {
+        slug: String::from("getting-started-with-newtech"),
+        images: ImageOutput::PostImages,
+        footnotes: FootnoteOutput::Section,
+        typography: Typography::default(),
+        callout_component: String::from(DEFAULT_CALLOUT_COMPONENT),
+    };
+    let output = convert_str(mdx, &options).unwrap();
+    assert!(output.frontmatter.contains(
+        "export const headings = [
+  { depth: 2, slug: 'installing', text: 'Installing' },
+  { depth: 3, slug: 'using-cargo', text: 'Using cargo' },
+  { depth: 2, slug: 'feedback', text: 'Feedback' },
+];"
+    ));
+
+    // with no headings to list, the marker is dropped instead of rendering an empty outline
+    let mdx = "<TableOfContents />
+
+Just a paragraph.
+
+> <TableOfContents />
+";
+    let output = convert_str(mdx, &options).unwrap();
+    assert_eq!(
+        output.body,
+        "<p>Just a paragraph.</p>\n<blockquote>\n</blockquote>\n"
+    );
+}
+
+#[test]
+pub fn test_convert_str_locale() {
+    assert_eq!("fr".parse(), Ok(Locale::French));
+    assert_eq!("de-AT".parse(), Ok(Locale::German));
+    assert_eq!("EN_gb".parse(), Ok(Locale::English));
+    assert!("es".parse::<Locale>().is_err());
+
+    let mdx = "---
+title: Premiers pas
+lang: fr
+---
+
+## Qu'est-ce que \"MDX\" ? {#mdx}
+
+C'est \"simple\" : essayez !
+";
+    let options = ConvertOptions {
+        slug: String::from("premiers-pas"),
+        images: ImageOutput::PostImages,
+        footnotes: FootnoteOutput::Section,
+        typography: Typography {
+            locale: Locale::German,
+            ..Typography::smart()
+        },
+        callout_component: String::from(DEFAULT_CALLOUT_COMPONENT),
+    };
+
+    // the frontmatter language takes precedence
+    let output = convert_str(mdx, &options).unwrap();
+    assert_eq!(
+        output.body,
+        "<h2 id=\"mdx\"><Heading client:visible id=\"mdx\" text=\"Qu\u{2019}est&#x2011;ce que \u{ab}\u{202f}MDX\u{202f}\u{bb}\u{202f}?\"/></h2>
+<p>C\u{2019}est \u{ab}\u{202f}simple\u{202f}\u{bb}\u{202f}:&nbsp;essayez\u{202f}!</p>
+"
+    );
+
+    // headings follow the locale, even without typography rules
+    let options = ConvertOptions {
+        typography: Typography {
+            locale: Locale::German,
+            ..Typography::default()
+        },
+        callout_component: String::from(DEFAULT_CALLOUT_COMPONENT),
+        ..options
+    };
+    let output = convert_str(&mdx.replace("lang: fr\n", ""), &options).unwrap();
+    assert_eq!(
+        output.body,
+        "<h2 id=\"mdx\"><Heading client:visible id=\"mdx\" text=\"Qu\u{2019}est&#x2011;ce que \u{201e}MDX\u{201c}\\u00a0?\"/></h2>
+<p>C'est \"simple\" : essayez !</p>
+"
+    );
+}
+
+#[test]
+pub fn test_slug_from_input_file_path() {
+    assert_eq!(
+        slug_from_input_file_path(&"posts/medium-format/index.mdx").ok(),
+        Some("medium-format")
+    );
     assert_eq!(
-        format_heading_widows(title),
-        "Introducing the zoo\\u2019s new elephant"
+        slug_from_input_file_path(&"posts/medium-format.mdx").ok(),
+        Some("medium-format")
+    );
+
+    // an index file in the working directory has no directory name to give the slug
+    match slug_from_input_file_path(&"index.mdx") {
+        Err(ParseError::Slug { path }) => assert_eq!(path, "index.mdx"),
+        other => panic!("Expected a slug error, got {other:?}"),
+    }
+}
+
+#[test]
+pub fn test_parse_document_lists() {
+    let mdx = "* Agfa *Isolette*,
+  a folder
+ * Mamiya 6
+    + 75 mm lens
++ Rolleiflex
+
+- Tight
+- list
+
+1) Loose
+
+2) list
+3. New list
+
+Chapter
+1984. was not a list
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let item = |children: Vec<Block>| ListItem {
+        checked: None,
+        children,
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![
+                    item(vec![Block::Paragraph(vec![
+                        text("Agfa "),
+                        Inline::Emphasis(vec![text("Isolette")]),
+                        text(","),
+                        Inline::SoftBreak,
+                        text("a folder")
+                    ])]),
+                    item(vec![
+                        Block::Paragraph(vec![text("Mamiya 6")]),
+                        Block::List(List {
+                            list_type: ListType::Unordered,
+                            start: None,
+                            loose: false,
+                            items: vec![item(vec![Block::Paragraph(vec![text("75 mm lens")])])]
+                        })
+                    ])
+                ]
+            }),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![item(vec![Block::Paragraph(vec![text("Rolleiflex")])])]
+            }),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![
+                    item(vec![Block::Paragraph(vec![text("Tight")])]),
+                    item(vec![Block::Paragraph(vec![text("list")])])
+                ]
+            }),
+            Block::List(List {
+                list_type: ListType::Ordered,
+                start: Some(String::from("1")),
+                loose: true,
+                items: vec![
+                    item(vec![Block::Paragraph(vec![text("Loose")])]),
+                    item(vec![Block::Paragraph(vec![text("list")])])
+                ]
+            }),
+            Block::List(List {
+                list_type: ListType::Ordered,
+                start: Some(String::from("3")),
+                loose: false,
+                items: vec![item(vec![Block::Paragraph(vec![text("New list")])])]
+            }),
+            Block::Paragraph(vec![
+                text("Chapter"),
+                Inline::SoftBreak,
+                text("1984. was not a list")
+            ])
+        ]
+    );
+
+    // a blockquote marker ends the list rather than lazily continuing the item
+    let mdx = "- a\n> quote\n\n- b\n> [!NOTE]\n";
+    let list = |value: &str| {
+        Block::List(List {
+            list_type: ListType::Unordered,
+            start: None,
+            loose: false,
+            items: vec![item(vec![Block::Paragraph(vec![text(value)])])],
+        })
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            list("a"),
+            Block::BlockQuote(BlockQuote {
+                callout: None,
+                children: vec![Block::Paragraph(vec![text("quote")])]
+            }),
+            list("b"),
+            Block::BlockQuote(BlockQuote {
+                callout: Some(CalloutType::Note),
+                children: Vec::new()
+            })
+        ]
+    );
+
+    // indented code after a blank line in an item makes the list loose
+    let mdx = "- item\n\n      code\n- next\n";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![Block::List(List {
+            list_type: ListType::Unordered,
+            start: None,
+            loose: true,
+            items: vec![
+                item(vec![
+                    Block::Paragraph(vec![text("item")]),
+                    Block::CodeBlock(CodeBlock {
+                        lines: vec![String::from("code")],
+                        ..Default::default()
+                    })
+                ]),
+                item(vec![Block::Paragraph(vec![text("next")])])
+            ]
+        })]
     );
+}
+
+#[test]
+pub fn test_parse_document_list_item_content() {
+    let mdx = "1. Install:
+
+   ```shell
+   cargo install
+   ```
+
+2. Check:
+
+   | a |
+   | - |
+   | 1 |
+
+   Then carry on.
+lazily.
+
+Paragraph after.
+
+    let indented = true;
+
+
+    let more = 1;
+
+- a
+# Heading
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::List(List {
+                list_type: ListType::Ordered,
+                start: Some(String::from("1")),
+                loose: true,
+                items: vec![
+                    ListItem {
+                        checked: None,
+                        children: vec![
+                            Block::Paragraph(vec![text("Install:")]),
+                            Block::CodeBlock(CodeBlock {
+                                language: Some(String::from("shell")),
+                                lines: vec![String::from("cargo install")],
+                                ..Default::default()
+                            })
+                        ]
+                    },
+                    ListItem {
+                        checked: None,
+                        children: vec![
+                            Block::Paragraph(vec![text("Check:")]),
+                            Block::Table(Table {
+                                alignments: vec![None],
+                                head: vec![TableRow {
+                                    cells: vec![vec![text("a")]]
+                                }],
+                                body: vec![TableRow {
+                                    cells: vec![vec![text("1")]]
+                                }]
+                            }),
+                            Block::Paragraph(vec![
+                                text("Then carry on."),
+                                Inline::SoftBreak,
+                                text("lazily.")
+                            ])
+                        ]
+                    }
+                ]
+            }),
+            Block::Paragraph(vec![text("Paragraph after.")]),
+            Block::CodeBlock(CodeBlock {
+                lines: vec![
+                    String::from("let indented = true;"),
+                    String::new(),
+                    String::new(),
+                    String::from("let more = 1;")
+                ],
+                ..Default::default()
+            }),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![ListItem {
+                    checked: None,
+                    children: vec![Block::Paragraph(vec![text("a")])]
+                }]
+            }),
+            Block::Heading(Heading {
+                level: 1,
+                id: String::from("heading"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![text("Heading")]
+            })
+        ]
+    );
+}
+
+#[test]
+pub fn test_parse_document_footnotes() {
+    let mdx = "Film[^film] is fun.[^note] See also[^film] and [^missing].
+
+[^film]: Mostly 35 mm.
+
+    Sometimes 120.
+[^note]: Short note.
+[^unused]: Never referenced.
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let reference = |number: usize, occurrence: usize| {
+        Inline::FootnoteReference(FootnoteReference {
+            number,
+            occurrence,
+            sidenote: None,
+        })
+    };
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![Block::Paragraph(vec![
+            text("Film"),
+            reference(1, 1),
+            text(" is fun."),
+            reference(2, 1),
+            text(" See also"),
+            reference(1, 2),
+            text(" and [^missing].")
+        ])]
+    );
+    assert_eq!(
+        document.footnotes,
+        vec![
+            Footnote {
+                children: vec![
+                    Block::Paragraph(vec![text("Mostly 35 mm.")]),
+                    Block::Paragraph(vec![text("Sometimes 120.")])
+                ],
+                references: 2
+            },
+            Footnote {
+                children: vec![Block::Paragraph(vec![text("Short note.")])],
+                references: 1
+            }
+        ]
+    );
+
+    // a blockquote ends the definition, rather than nesting the next definition inside it
+    let mdx = "x[^1] y[^2]\n\n[^1]: a\n> q\n[^2]: b\n";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Paragraph(vec![
+                text("x"),
+                reference(1, 1),
+                text(" y"),
+                reference(2, 1)
+            ]),
+            Block::BlockQuote(BlockQuote {
+                callout: None,
+                children: vec![Block::Paragraph(vec![text("q")])]
+            })
+        ]
+    );
+    assert_eq!(
+        document.footnotes,
+        vec![
+            Footnote {
+                children: vec![Block::Paragraph(vec![text("a")])],
+                references: 1
+            },
+            Footnote {
+                children: vec![Block::Paragraph(vec![text("b")])],
+                references: 1
+            }
+        ]
+    );
+    let document = parse_document("x[^1]\n\n[^1]: a\n> [!NOTE]\n[^2]: b\n", "<input>").unwrap();
+    assert_eq!(document.footnotes.len(), 1);
+    assert!(matches!(
+        document.blocks[1],
+        Block::BlockQuote(BlockQuote {
+            callout: Some(CalloutType::Note),
+            ..
+        })
+    ));
+}
 
-    let title = "\"Introducing\" the zoo's new elephant";
+#[test]
+pub fn test_parse_footnote_definition() {
     assert_eq!(
-        format_heading_widows(title),
-        "\\u201cIntroducing\\u201d the zoo\\u2019s new elephant"
+        parse_footnote_definition("[^Film]: Mostly 35 mm."),
+        Ok(("Mostly 35 mm.", String::from("^film")))
     );
+    assert!(parse_footnote_definition("[film]: https://example.com").is_err());
+    assert!(parse_link_reference_definition("[^film]: https://example.com").is_err());
+}
+
+#[test]
+pub fn test_parse_document_link_references() {
+    let mdx = "Read the [Getting Started guide][Guide] or the [FAQ][], see note [1].
+
+[guide]: https://www.example.com/guide \"Getting Started\"
+[FAQ]: /faq
+";
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![Block::Paragraph(vec![
+            Inline::Text(String::from("Read the ")),
+            Inline::Link(Link {
+                href: String::from("https://www.example.com/guide"),
+                attributes: String::from(
+                    "href=\"https://www.example.com/guide\" title=\"Getting Started\""
+                ),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("Getting Started guide"))]
+            }),
+            Inline::Text(String::from(" or the ")),
+            Inline::Link(Link {
+                href: String::from("/faq"),
+                attributes: String::from("href=\"/faq\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("FAQ"))]
+            }),
+            Inline::Text(String::from(", see note [1]."))
+        ])]
+    );
+
+    // references inside strikethrough resolve too
+    let document = parse_document("~~[Old FAQ][faq]~~\n\n[faq]: /faq\n", "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![Block::Paragraph(vec![Inline::Strikethrough(vec![
+            Inline::Link(Link {
+                href: String::from("/faq"),
+                attributes: String::from("href=\"/faq\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![Inline::Text(String::from("Old FAQ"))]
+            })
+        ])])]
+    );
+
+    // references with a label must have a definition
+    let mdx = "## Links\n\nRead the [guide][getting started].\n";
+    match parse_document(mdx, "<input>") {
+        Err(ParseError::Source(error)) => {
+            assert_eq!(
+                error.kind,
+                ParseErrorKind::UnresolvedLinkReference(String::from("getting started"))
+            );
+            assert_eq!(error.line, 3);
+            assert_eq!(error.column, 10);
+            assert_eq!(error.span().len(), 24);
+        }
+        _ => panic!("Expected unresolved link reference error"),
+    }
 }
 
 #[test]
-pub fn test_format_inline_wrap_text_number_range() {
-    let line = "<InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} />";
+pub fn test_parse_link_reference_definition() {
+    let mdx_line = "[Getting  Started]: <https://www.example.com/guide> 'Guide'";
     assert_eq!(
-        format_inline_wrap_text_number_range(line),
-        Ok(("", String::from("<InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} />")))
+        parse_link_reference_definition(mdx_line),
+        Ok((
+            "",
+            (
+                String::from("getting started"),
+                LinkDefinition {
+                    href: String::from("https://www.example.com/guide"),
+                    title: Some(String::from("Guide"))
+                }
+            )
+        ))
     );
 
-    let line =
-        "<InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+    let mdx_line = "[Getting Started]: https://www.example.com/guide and more";
+    assert!(parse_link_reference_definition(mdx_line).is_err());
+}
+
+#[test]
+pub fn test_parse_callout_first_line() {
     assert_eq!(
-        format_inline_wrap_text_number_range(line),
-        Ok(("", String::from("<InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+        parse_callout_first_line("[!WARNING]"),
+        Ok(("", CalloutType::Warning))
     );
+    assert_eq!(
+        parse_callout_first_line("[!note]  "),
+        Ok(("", CalloutType::Note))
+    );
+    assert!(parse_callout_first_line("[!NOTE] Take care").is_err());
+}
 
-    let line =
-        "Interestingly, <InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+#[test]
+pub fn test_strip_block_quote_markers() {
     assert_eq!(
-        format_inline_wrap_text_number_range(line),
-        Ok(("", String::from("Interestingly, <InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+        strip_block_quote_markers("> > Nested quote", usize::MAX),
+        (2, "Nested quote")
     );
-    let line =
-        "Interestingly <InlineCodeFragment code={`x`} /> and <InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+    assert_eq!(strip_block_quote_markers(">>quote", 1), (1, ">quote"));
     assert_eq!(
-        format_inline_wrap_text_number_range(line),
-        Ok(("", String::from("Interestingly <InlineCodeFragment code={`x`} /> and <InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+        strip_block_quote_markers("No quote", usize::MAX),
+        (0, "No quote")
     );
 }
 
+#[test]
+pub fn test_parse_code_fence() {
+    assert_eq!(
+        parse_code_fence("   ~~~~ rust"),
+        Ok((
+            " rust",
+            CodeFence {
+                character: '~',
+                length: 4,
+                indentation: 3
+            }
+        ))
+    );
+    assert!(parse_code_fence("``").is_err());
+    assert!(parse_code_fence("    ```").is_err());
+}
+
+#[test]
+pub fn test_parse_thematic_break() {
+    assert_eq!(parse_thematic_break("---"), Ok(("", '-')));
+    assert_eq!(parse_thematic_break("   * * *\t"), Ok(("", '*')));
+    assert_eq!(parse_thematic_break("_____"), Ok(("", '_')));
+    assert!(parse_thematic_break("--").is_err());
+    assert!(parse_thematic_break("    ---").is_err());
+    assert!(parse_thematic_break("-*-").is_err());
+    assert!(parse_thematic_break("--- a").is_err());
+}
+
+#[test]
+pub fn test_parse_fenced_code_block_last_line() {
+    let fence = CodeFence {
+        character: '`',
+        length: 4,
+        indentation: 0,
+    };
+    assert!(parse_fenced_code_block_last_line("`````  ", fence).is_ok());
+    assert!(parse_fenced_code_block_last_line("```", fence).is_err());
+    assert!(parse_fenced_code_block_last_line("~~~~", fence).is_err());
+    assert!(parse_fenced_code_block_last_line("```` rust", fence).is_err());
+    assert!(parse_fenced_code_block_first_line("``` `rust`").is_err());
+}
+
 #[test]
 pub fn test_parse_fenced_code_block_first_line() {
     let mdx_line = "```plaintext {5,7} \".env\" [A code block all about maths]";
@@ -602,7 +2001,7 @@ pub fn test_parse_href_scheme() {
     let href = "/home";
     assert_eq!(
         parse_href_scheme(href),
-        Err(Err::Error(Error::new(href, ErrorKind::Tag)))
+        Err(Err::Error(LineError::new(href, ErrorKind::Tag)))
     );
 }
 
@@ -666,34 +2065,82 @@ pub fn test_parse_html_tag_content() {
 pub fn test_parse_mdx_line() {
     let mdx_line = "# Getting Started with NewTech  ";
     assert_eq!(
-            parse_mdx_line(mdx_line),
-        Some((
-            String::from(
-                "<h1 id=\"getting-started-with-newtech-\"><Heading client:visible id=\"getting-started-with-newtech-\" text=\"Getting Started with NewTech\"/></h1>"
-            ),
+        parse_mdx_line(mdx_line, None),
+        Ok(Some((
+            Block::Heading(Heading {
+                level: 1,
+                id: String::from("getting-started-with-newtech"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![Inline::Text(String::from("Getting Started with NewTech"))]
+            }),
             LineType::Heading,
             1
-        ))
-    );
-
-    let mdx_line = "### 😕 What Does All This Mean?";
-    assert_eq!(
-        parse_mdx_line(mdx_line),
-        Some((
-            String::from(
-                "<h3 id=\"confused-what-does-all-this-mean\"><Heading client:visible id=\"confused-what-does-all-this-mean\" text=\"😕 What Does All This Mean?\"/></h3>"
-            ),
+        )))
+    );
+
+    let mdx_line = "### Using `cargo`";
+    assert_eq!(
+        parse_mdx_line(mdx_line, None),
+        Ok(Some((
+            Block::Heading(Heading {
+                level: 3,
+                id: String::from("using-cargo"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![
+                    Inline::Text(String::from("Using ")),
+                    Inline::Code(String::from("cargo"))
+                ]
+            }),
             LineType::Heading,
             3
-        ))
+        )))
+    );
+
+    let mdx_line = "## The `` ` `` and \\` characters";
+    assert_eq!(
+        parse_mdx_line(mdx_line, None),
+        Ok(Some((
+            Block::Heading(Heading {
+                level: 2,
+                id: String::from("the-and-characters"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![
+                    Inline::Text(String::from("The ")),
+                    Inline::Code(String::from("`")),
+                    Inline::Text(String::from(" and ` characters"))
+                ]
+            }),
+            LineType::Heading,
+            2
+        )))
     );
 
     let mdx_line = "NewTech was first set up to solve the common problem coming up for identifiers in computer science.";
     assert_eq!(
-            parse_mdx_line(mdx_line),
-            Some((String::from("<p>NewTech was first set up to solve the common problem coming up for identifiers in computer science.</p>"),
-                LineType::Paragraph, 0))
-        );
+        parse_mdx_line(mdx_line, None),
+        Ok(Some((
+            Block::Paragraph(vec![Inline::Text(String::from(mdx_line))]),
+            LineType::Paragraph,
+            0
+        )))
+    );
+
+    // blank lines end open blocks
+    assert_eq!(parse_mdx_line("", None), Ok(None));
+    assert_eq!(parse_mdx_line("   ", None), Ok(None));
+
+    // unrecognised HTML block elements are errors rather than panics
+    let mdx_line = "<section class=\"wide\">";
+    assert_eq!(
+        parse_mdx_line(mdx_line, None),
+        Err(LineError {
+            input: mdx_line,
+            kind: ParseErrorKind::UnrecognisedBlockElement(String::from("section"))
+        })
+    );
 }
 
 #[test]
@@ -712,39 +2159,273 @@ pub fn test_parse_heading_text() {
 }
 
 #[test]
-pub fn test_parse_inline_wrap_segment() {
-    let mdx_line =
-        "first** set up to solve the common problem coming up for identifiers in computer science.";
-    assert_eq!(parse_inline_wrap_segment(mdx_line, "**"), Ok(("", ("first", " set up to solve the common problem coming up for identifiers in computer science."))));
+pub fn test_strip_heading_closing_sequence() {
+    assert_eq!(
+        strip_heading_closing_sequence("Getting Started ##  "),
+        "Getting Started"
+    );
+    assert_eq!(strip_heading_closing_sequence("C#"), "C#");
+    assert_eq!(strip_heading_closing_sequence("Issue \\#"), "Issue \\#");
+    assert_eq!(strip_heading_closing_sequence("###"), "");
 }
 
 #[test]
-pub fn test_parse_up_to_inline_wrap_segment() {
-    let mdx_line = "NewTech was **first** set up to solve the common problem coming up for identifiers in computer science.";
-    assert_eq!(parse_up_to_inline_wrap_segment(mdx_line, "**"), Ok(("", ("NewTech was ", "first** set up to solve the common problem coming up for identifiers in computer science."))));
+pub fn test_parse_heading_attributes() {
+    assert_eq!(
+        parse_heading_attributes("{#custom-id .wide .centred}"),
+        Ok(("", (Some("custom-id"), vec!["wide", "centred"])))
+    );
+    assert_eq!(
+        parse_heading_attributes("{ .wide }"),
+        Ok(("", (None, vec!["wide"])))
+    );
+    assert!(parse_heading_attributes("{}").is_err());
+    assert!(parse_heading_attributes("{#custom-id} more").is_err());
+    assert!(parse_heading_attributes("{width=100}").is_err());
+}
+
+#[test]
+pub fn test_parse_setext_heading_underline() {
+    assert_eq!(parse_setext_heading_underline("=====  "), Ok(("", 1)));
+    assert_eq!(parse_setext_heading_underline("   -"), Ok(("", 2)));
+    assert!(parse_setext_heading_underline("- - -").is_err());
+    assert!(parse_setext_heading_underline("==-").is_err());
+    assert!(parse_setext_heading_underline("    ===").is_err());
+}
+
+#[test]
+pub fn test_parse_document_headings() {
+    let mdx = "## What Does All This Mean? ##
+
+Getting Started
+with `cargo`
+===
+
+- Installing
+  ---
+
+> Quoted
+---
+";
+    let text = |value: &str| Inline::Text(String::from(value));
+    let document = parse_document(mdx, "<input>").unwrap();
+    assert_eq!(
+        document.blocks,
+        vec![
+            Block::Heading(Heading {
+                level: 2,
+                id: String::from("what-does-all-this-mean"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![text("What Does All This Mean?")]
+            }),
+            Block::Heading(Heading {
+                level: 1,
+                id: String::from("getting-started-with-cargo"),
+                classes: Vec::new(),
+                custom_id: false,
+                content: vec![
+                    text("Getting Started with "),
+                    Inline::Code(String::from("cargo"))
+                ]
+            }),
+            Block::List(List {
+                list_type: ListType::Unordered,
+                start: None,
+                loose: false,
+                items: vec![ListItem {
+                    checked: None,
+                    children: vec![Block::Heading(Heading {
+                        level: 2,
+                        id: String::from("installing"),
+                        classes: Vec::new(),
+                        custom_id: false,
+                        content: vec![text("Installing")]
+                    })]
+                }]
+            }),
+            Block::BlockQuote(BlockQuote {
+                callout: None,
+                children: vec![Block::Paragraph(vec![text("Quoted")])]
+            }),
+            Block::ThematicBreak,
+        ]
+    );
 }
 
 #[test]
 pub fn test_parse_inline_wrap_text() {
-    let mdx_line = "NewTech was **first** set up to solve the **common problem** coming up for identifiers in computer science.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("NewTech was <strong>first</strong> set up to solve the <strong>common problem</strong> coming up for identifiers in computer science."))));
+    let mdx_line = "NewTech was **first** set up to solve the **common problem** coming up.";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("NewTech was ")),
+                Inline::Strong(vec![Inline::Text(String::from("first"))]),
+                Inline::Text(String::from(" set up to solve the ")),
+                Inline::Strong(vec![Inline::Text(String::from("common problem"))]),
+                Inline::Text(String::from(" coming up."))
+            ]
+        ))
+    );
 
     let mdx_line = "NewTech was first set up to solve the common problem coming up for identifiers in computer science.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("NewTech was first set up to solve the common problem coming up for identifiers in computer science."))));
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok(("", vec![Inline::Text(String::from(mdx_line))]))
+    );
 
-    let mdx_line = "NewTech was first set up to *solve* the common problem coming up for identifiers in *computer* science.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("NewTech was first set up to <em>solve</em> the common problem coming up for identifiers in <em>computer</em> science."))));
+    let mdx_line = "NewTech was first set up to *solve* the common problem.";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("NewTech was first set up to ")),
+                Inline::Emphasis(vec![Inline::Text(String::from("solve"))]),
+                Inline::Text(String::from(" the common problem."))
+            ]
+        ))
+    );
 
-    let mdx_line = "To me `E=mc^2` rather than `F=ma` is **the** most important equation.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("To me <InlineCodeFragment code={`E=mc^2`} /> rather than <InlineCodeFragment code={`F=ma`} /> is <strong>the</strong> most important equation."))));
+    // unmatched delimiters are left as text
+    let mdx_line = "NewTech costs 5 * 3 dollars.";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok(("", vec![Inline::Text(String::from(mdx_line))]))
+    );
 
-    let mdx_line =
-        "On <a href=\"www.example.com\">our site</a>, you can see how `console.log()` works.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("On <a href=\"www.example.com\">our site</a>, you can see how <InlineCodeFragment code={`console.log()`} /> works."))));
+    let mdx_line = "See note [1] for the [*full* story](/story).";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("See note ")),
+                Inline::LinkReference(LinkReference {
+                    label: String::from("1"),
+                    shortcut: true,
+                    source: String::from("[1]"),
+                    content: vec![Inline::Text(String::from("1"))]
+                }),
+                Inline::Text(String::from(" for the ")),
+                Inline::Link(Link {
+                    href: String::from("/story"),
+                    attributes: String::from("href=\"/story\""),
+                    has_rel: false,
+                    has_target: false,
+                    content: vec![
+                        Inline::Emphasis(vec![Inline::Text(String::from("full"))]),
+                        Inline::Text(String::from(" story"))
+                    ]
+                }),
+                Inline::Text(String::from("."))
+            ]
+        ))
+    );
 
     let mdx_line =
         "See our <a href=\"www.example.com\">latest `console.log()` example</a> if you like.";
-    assert_eq!(parse_inline_wrap_text(mdx_line), Ok(("", String::from("See our <a href=\"www.example.com\">latest <InlineCodeFragment code={`console.log()`} /> example</a> if you like."))));
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("See our ")),
+                Inline::Link(Link {
+                    href: String::from("www.example.com"),
+                    attributes: String::from("href=\"www.example.com\""),
+                    has_rel: false,
+                    has_target: false,
+                    content: vec![
+                        Inline::Text(String::from("latest ")),
+                        Inline::Code(String::from("console.log()")),
+                        Inline::Text(String::from(" example"))
+                    ]
+                }),
+                Inline::Text(String::from(" if you like."))
+            ]
+        ))
+    );
+
+    let mdx_line =
+        "Film is ~~cheap~~ pricey, see https://example.com/film_(35mm). Or http:// alone.";
+    let external_link = |url: &str| {
+        Inline::Link(Link {
+            href: String::from(url),
+            attributes: format!("href=\"{url}\""),
+            has_rel: false,
+            has_target: false,
+            content: vec![Inline::Text(String::from(url))],
+        })
+    };
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                Inline::Text(String::from("Film is ")),
+                Inline::Strikethrough(vec![Inline::Text(String::from("cheap"))]),
+                Inline::Text(String::from(" pricey, see ")),
+                external_link("https://example.com/film_(35mm)"),
+                Inline::Text(String::from(". Or http:// alone."))
+            ]
+        ))
+    );
+
+    // a URL already in link text is not linked again
+    let mdx_line = "<https://example.com> and [https://example.com](https://example.com)";
+    assert_eq!(
+        parse_inline_wrap_text(mdx_line),
+        Ok((
+            "",
+            vec![
+                external_link("https://example.com"),
+                Inline::Text(String::from(" and ")),
+                external_link("https://example.com")
+            ]
+        ))
+    );
+}
+
+#[test]
+pub fn test_form_unordered_list_line() {
+    let mdx_line = "- [x] Load the **film**";
+    assert_eq!(
+        form_unordered_list_line(mdx_line),
+        Ok((
+            "Load the **film**",
+            (
+                Block::List(List {
+                    list_type: ListType::Unordered,
+                    start: None,
+                    loose: false,
+                    items: vec![ListItem {
+                        checked: Some(true),
+                        children: Vec::new()
+                    }]
+                }),
+                LineType::UnorderedListItem,
+                0
+            )
+        ))
+    );
+
+    let mdx_line = "  - [ ] Develop";
+    let (_, (block, _, _)) = form_unordered_list_line(mdx_line).unwrap();
+    let Block::List(List { items, .. }) = block else {
+        panic!("expected a list");
+    };
+    assert_eq!(items[0].checked, Some(false));
+
+    // a marker needs following text
+    let mdx_line = "- [ ]";
+    let (_, (block, _, _)) = form_unordered_list_line(mdx_line).unwrap();
+    let Block::List(List { items, .. }) = block else {
+        panic!("expected a list");
+    };
+    assert_eq!(items[0].checked, None);
 }
 
 #[test]
@@ -778,6 +2459,15 @@ pub fn test_parse_ordered_list_text() {
         parse_ordered_list_text(list_line_mdx),
         Ok(("third of all", (2, "3")))
     );
+
+    let list_line_mdx = "10) tenth of all";
+    assert_eq!(
+        parse_ordered_list_text(list_line_mdx),
+        Ok(("tenth of all", (0, "10")))
+    );
+
+    let list_line_mdx = "1234567890. too long";
+    assert!(parse_ordered_list_text(list_line_mdx).is_err());
 }
 
 #[test]
@@ -877,38 +2567,53 @@ pub fn test_parse_table_cell() {
         parse_table_cell(mdx_line),
         Ok(("Central London |", "1 January "))
     );
+
+    let mdx_line = "a \\| b | c |";
+    assert_eq!(parse_table_cell(mdx_line), Ok(("c |", "a \\| b ")));
 }
 
 #[test]
 pub fn test_parse_table_column_alignment() {
-    let mdx_line = ":--- | ---: |";
     assert_eq!(
-        parse_table_column_alignment(mdx_line),
-        Ok(("---: |", TableAlign::Left))
+        parse_table_column_alignment(":---"),
+        Ok(("", Some(TableAlign::Left)))
     );
-
-    let mdx_line = ":---: | ---: |";
     assert_eq!(
-        parse_table_column_alignment(mdx_line),
-        Ok(("---: |", TableAlign::Centre))
+        parse_table_column_alignment(" :-: "),
+        Ok(("", Some(TableAlign::Centre)))
     );
-
-    let mdx_line = "---: | ---: |";
     assert_eq!(
-        parse_table_column_alignment(mdx_line),
-        Ok(("---: |", TableAlign::Right))
+        parse_table_column_alignment("-----:"),
+        Ok(("", Some(TableAlign::Right)))
     );
+    assert_eq!(parse_table_column_alignment("-"), Ok(("", None)));
+    assert!(parse_table_column_alignment("-- -").is_err());
+    assert!(parse_table_column_alignment("::").is_err());
 }
 
 #[test]
 pub fn test_parse_table_header_row() {
-    let mdx_line = "| :--- | :---: | ---: |";
+    let mdx_line = "| :--- | :---: | ---: | --- |";
     let (remaining_line, result) = parse_table_header_row(mdx_line).unwrap();
     assert_eq!(remaining_line, "");
-    assert_eq!(result.len(), 3);
-    assert_eq!(result[0], TableAlign::Left);
-    assert_eq!(result[1], TableAlign::Centre);
-    assert_eq!(result[2], TableAlign::Right);
+    assert_eq!(
+        result,
+        vec![
+            Some(TableAlign::Left),
+            Some(TableAlign::Centre),
+            Some(TableAlign::Right),
+            None
+        ]
+    );
+
+    let mdx_line = "-|:-";
+    assert_eq!(
+        parse_table_header_row(mdx_line),
+        Ok(("", vec![None, Some(TableAlign::Left)]))
+    );
+
+    // a delimiter row needs a pipe, so a thematic break is not a table
+    assert!(parse_table_header_row("---").is_err());
 }
 
 #[test]
@@ -920,6 +2625,12 @@ pub fn test_parse_table_line() {
     assert_eq!(result[0], "1 January ");
     assert_eq!(result[1], "Central London ");
     assert_eq!(result[2], "Sunny ");
+
+    let mdx_line = "1 January|Central London";
+    assert_eq!(
+        parse_table_line(mdx_line),
+        Ok(("", vec!["1 January", "Central London"]))
+    );
 }
 
 #[test]
@@ -935,6 +2646,18 @@ pub fn test_parse_unordered_list_text() {
         parse_unordered_list_text(list_line_mdx),
         Ok(("first of all  ", 2))
     );
+
+    let list_line_mdx = "* first of all";
+    assert_eq!(
+        parse_unordered_list_text(list_line_mdx),
+        Ok(("first of all", 0))
+    );
+
+    let list_line_mdx = "+ first of all";
+    assert_eq!(
+        parse_unordered_list_text(list_line_mdx),
+        Ok(("first of all", 0))
+    );
 }
 
 #[test]
@@ -955,28 +2678,6 @@ pub fn test_remove_html_tags() {
     assert_eq!(remove_html_tags(mdx_line), Ok(("it's me", "Hello ")));
 }
 
-#[test]
-pub fn test_segment_strong_emphasis_line() {
-    let mdx_line = "NewTech was **first** set up to solve the **common problem** coming up for identifiers in computer science.";
-    assert_eq!(segment_strong_emphasis_line(mdx_line), Ok(("", ("NewTech was ", "first", " set up to solve the **common problem** coming up for identifiers in computer science."))));
-}
-
-#[test]
-pub fn test_segment_emphasis_line() {
-    let mdx_line = "NewTech was first set up to *solve* the common problem coming up for identifiers in *computer* science.";
-    assert_eq!(
-        segment_emphasis_line(mdx_line),
-        Ok((
-            "",
-            (
-                "NewTech was first set up to ",
-                "solve",
-                " the common problem coming up for identifiers in *computer* science."
-            )
-        ))
-    );
-}
-
 #[test]
 pub fn test_slugify_title() {
     let title = "🏄🏽 All about Surf";
@@ -0,0 +1,414 @@
+#[cfg(test)]
+mod tests;
+
+use crate::parser::{
+    ast::{Block, Document, Inline, Link, LinkReference},
+    error::{IResult, LineError},
+    Locale, Typography, TypographyRule,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::{alphanumeric0, digit1, hex_digit1, one_of, satisfy, space0},
+    combinator::{not, opt, peek, recognize},
+    error::ErrorKind,
+    sequence::{pair, preceded, terminated},
+    Err, Parser,
+};
+
+/// En dash, with thin spaces either side, between the numbers of a range
+pub const NUMBER_RANGE_DASH: &str = "&thinsp;&ndash;&thinsp;";
+
+// code spans, images and footnote references sit in the text like a word
+const OBJECT_CHARACTER: char = '\u{fffc}';
+
+// in French, inside guillemets and before `:`, `;`, `!` and `?`
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+// opening and closing double quotes, then opening and closing single quotes
+fn quotation_marks(locale: Locale) -> [&'static str; 4] {
+    match locale {
+        Locale::English => ["\u{201c}", "\u{201d}", "\u{2018}", "\u{2019}"],
+        Locale::French => ["\u{ab}\u{202f}", "\u{202f}\u{bb}", "\u{201c}", "\u{201d}"],
+        Locale::German => ["\u{201e}", "\u{201c}", "\u{201a}", "\u{2018}"],
+    }
+}
+
+// a quote opens at the start of the text, or after whitespace, an opening quote or bracket, or a dash
+fn opens_quote(previous: Option<char>) -> bool {
+    previous.map_or(true, |value| {
+        value.is_whitespace()
+            || "([{\u{ab}\u{201c}\u{201e}\u{2018}\u{201a}\u{2013}\u{2014}".contains(value)
+    })
+}
+
+// length of an HTML or JSX tag, including any quoted attribute values, at the start of `text`
+fn tag_length(text: &str) -> Option<usize> {
+    let mut characters = text.char_indices().skip(1);
+    match characters.next() {
+        Some((_, value)) if value.is_ascii_alphabetic() || value == '/' || value == '!' => {}
+        _ => return None,
+    }
+    let mut quote: Option<char> = None;
+    for (index, value) in characters {
+        match (quote, value) {
+            (Some(open_quote), _) if value == open_quote => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(value),
+            (None, '>') => return Some(index + 1),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+// length of a `{…}` JSX expression, with any nested braces, at the start of `text`
+fn expression_length(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, value) in text.char_indices() {
+        match value {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// `&name;`, `&#n;` or `&#xh;` character reference
+fn parse_html_entity(text: &str) -> IResult<&str, &str> {
+    recognize((
+        tag("&"),
+        alt((
+            preceded(tag_no_case("#x"), hex_digit1),
+            preceded(tag("#"), digit1),
+            recognize(pair(satisfy(|c| c.is_ascii_alphabetic()), alphanumeric0)),
+        )),
+        tag(";"),
+    ))
+    .parse(text)
+}
+
+// length of a URL, which runs to the next whitespace, at the start of `text`
+fn url_length(text: &str) -> Option<usize> {
+    if ["http://", "https://", "mailto:", "www."]
+        .iter()
+        .any(|scheme| text.starts_with(scheme))
+    {
+        Some(text.find(char::is_whitespace).unwrap_or(text.len()))
+    } else {
+        None
+    }
+}
+
+// whole or decimal number
+fn parse_number(text: &str) -> IResult<&str, &str> {
+    recognize(pair(digit1, opt(pair(tag("."), digit1)))).parse(text)
+}
+
+/* a number ends at whitespace or punctuation, but not where it runs on into a word, or into a date,
+ * time or version number
+ */
+fn parse_number_end(text: &str) -> IResult<&str, ()> {
+    not(alt((
+        satisfy(char::is_alphanumeric),
+        terminated(one_of("-./:,"), satisfy(|c| c.is_ascii_digit())),
+    )))
+    .parse(text)
+}
+
+// second number of dimensions or a range, which may take a unit, without consuming it
+fn peek_second_number<'a>(text: &'a str, units: &[String]) -> IResult<&'a str, &'a str> {
+    match peek(terminated(parse_number, parse_number_end)).parse(text) {
+        Ok(value) => Ok(value),
+        Err(_) => {
+            let (_, (number, _)) = parse_number_unit(text, units)?;
+            Ok((text, number))
+        }
+    }
+}
+
+// `6x9` or `60 × 70` dimensions, returning the first number and the spacing either side of the `x`
+fn parse_dimensions<'a>(
+    text: &'a str,
+    units: &[String],
+) -> IResult<&'a str, (&'a str, &'a str, &'a str)> {
+    let (remaining_text, (number, space_before, separator, space_after)) =
+        (parse_number, space0, alt((tag("x"), tag("\u{d7}"))), space0).parse(text)?;
+
+    // `0x10` is a hexadecimal number
+    if number == "0" && space_before.is_empty() && separator == "x" {
+        return Err(Err::Error(LineError::new(text, ErrorKind::Verify)));
+    }
+    peek_second_number(remaining_text, units)?;
+    Ok((remaining_text, (number, space_before, space_after)))
+}
+
+// numbers with a leading zero, or in the `555-1234` shape, are codes or phone numbers
+fn is_phone_number(start: &str, end: &str) -> bool {
+    let leading_zero =
+        |number: &str| number.len() > 1 && number.starts_with('0') && !number.starts_with("0.");
+    leading_zero(start)
+        || leading_zero(end)
+        || (start.len() == 3 && end.len() == 4 && !start.contains('.') && !end.contains('.'))
+}
+
+/* `10-20` range, returning the start, which must be lower than the end, and which, with the end, is
+ * not part of a phone number
+ */
+fn parse_number_range<'a>(text: &'a str, units: &[String]) -> IResult<&'a str, &'a str> {
+    let (remaining_text, (start, _)) = (parse_number, tag("-")).parse(text)?;
+    let (_, end) = peek_second_number(remaining_text, units)?;
+    match (start.parse::<f64>(), end.parse::<f64>()) {
+        (Ok(start_value), Ok(end_value))
+            if start_value < end_value && !is_phone_number(start, end) =>
+        {
+            Ok((remaining_text, start))
+        }
+        _ => Err(Err::Error(LineError::new(text, ErrorKind::Verify))),
+    }
+}
+
+// number followed by one of `units`, returning the number and unit
+fn parse_number_unit<'a>(text: &'a str, units: &[String]) -> IResult<&'a str, (&'a str, &'a str)> {
+    let (remaining_text, (number, _)) = (parse_number, opt(tag(" "))).parse(text)?;
+    for unit in units {
+        if let Some(after_unit) = remaining_text.strip_prefix(unit.as_str()) {
+            if !after_unit.starts_with(char::is_alphanumeric) {
+                return Ok((after_unit, (number, &remaining_text[..unit.len()])));
+            }
+        }
+    }
+    Err(Err::Error(LineError::new(text, ErrorKind::Tag)))
+}
+
+/* applies the number rules to a number at the start of `text`, returning the formatted text, which
+ * for dimensions and ranges runs up to the second number, so that the second number can take a unit
+ */
+fn format_number<'a>(text: &'a str, typography: &Typography) -> Option<(&'a str, String)> {
+    let applies = |rule| typography.rules.contains(&rule);
+    if applies(TypographyRule::Dimensions) {
+        if let Ok((remaining_text, (number, space_before, space_after))) =
+            parse_dimensions(text, &typography.units)
+        {
+            return Some((
+                remaining_text,
+                format!("{number}{space_before}\u{d7}{space_after}"),
+            ));
+        }
+    }
+    if applies(TypographyRule::NumberRanges) {
+        if let Ok((remaining_text, start)) = parse_number_range(text, &typography.units) {
+            return Some((remaining_text, format!("{start}{NUMBER_RANGE_DASH}")));
+        }
+    }
+    if applies(TypographyRule::Units) {
+        if let Ok((remaining_text, (number, unit))) = parse_number_unit(text, &typography.units) {
+            return Some((remaining_text, format!("{number}&nbsp;{unit}")));
+        }
+    }
+    None
+}
+
+/* curls quotes, in the marks of the locale, and apostrophes, spaces French punctuation, replaces
+ * `--`, `---` and `...` with en and em dashes and ellipses, and formats numbers, following the
+ * chosen rules; tags, JSX expressions, HTML entities and URLs are copied unchanged, and `previous`
+ * carries the last character across inlines
+ */
+pub fn format_text(text: &str, previous: &mut Option<char>, typography: &Typography) -> String {
+    let applies = |rule| typography.rules.contains(&rule);
+    let mut result = String::with_capacity(text.len());
+    let mut index = 0;
+    while let Some(value) = text[index..].chars().next() {
+        let remaining_text = &text[index..];
+        let verbatim_length = match value {
+            '<' => tag_length(remaining_text),
+            '{' => expression_length(remaining_text),
+            '&' => parse_html_entity(remaining_text)
+                .ok()
+                .map(|(_, entity)| entity.len()),
+            'h' | 'm' | 'w' if opens_quote(*previous) || *previous == Some('<') => {
+                url_length(remaining_text)
+            }
+            _ => None,
+        };
+        if let Some(length) = verbatim_length {
+            let verbatim = &remaining_text[..length];
+            result.push_str(verbatim);
+            match value {
+                '<' => {}
+                // a space entity is followed as whitespace, and any other as a character of a word
+                '&' => {
+                    *previous = Some(
+                        if matches!(verbatim, "&nbsp;" | "&ensp;" | "&emsp;" | "&thinsp;") {
+                            '\u{a0}'
+                        } else {
+                            OBJECT_CHARACTER
+                        },
+                    );
+                }
+                _ => *previous = verbatim.chars().last(),
+            }
+            index += length;
+            continue;
+        }
+
+        // a number must start a word, and not continue a date, time or version number
+        if value.is_ascii_digit()
+            && !previous.is_some_and(|c| c.is_alphanumeric() || "-./:,".contains(c))
+        {
+            if let Some((final_text, formatted)) = format_number(remaining_text, typography) {
+                result.push_str(&formatted);
+                *previous = formatted.chars().last();
+                index = text.len() - final_text.len();
+                continue;
+            }
+        }
+
+        let next = remaining_text[value.len_utf8()..].chars().next();
+        if applies(TypographyRule::PunctuationSpaces)
+            && typography.locale == Locale::French
+            && ":;!?".contains(value)
+            && !next.is_some_and(char::is_alphanumeric)
+        {
+            match *previous {
+                // an existing space is made narrow and non-breaking
+                Some(' ') if result.ends_with(' ') => {
+                    result.pop();
+                    result.push(NARROW_NO_BREAK_SPACE);
+                }
+                Some(c) if !c.is_whitespace() && !":;!?".contains(c) => {
+                    result.push(NARROW_NO_BREAK_SPACE);
+                }
+                _ => {}
+            }
+        }
+
+        let marks = quotation_marks(typography.locale);
+        let quotes = applies(TypographyRule::Quotes);
+        let (replacement, length) =
+            if applies(TypographyRule::Dashes) && remaining_text.starts_with("---") {
+                ("\u{2014}", 3)
+            } else if applies(TypographyRule::Dashes) && remaining_text.starts_with("--") {
+                ("\u{2013}", 2)
+            } else if applies(TypographyRule::Ellipses) && remaining_text.starts_with("...") {
+                ("\u{2026}", 3)
+            } else {
+                let replacement = match value {
+                    '"' if quotes && opens_quote(*previous) => marks[0],
+                    '"' if quotes => marks[1],
+                    // a leading apostrophe, as in '90s, closes
+                    '\'' if quotes
+                        && opens_quote(*previous)
+                        && !next.is_some_and(|c| c.is_ascii_digit()) =>
+                    {
+                        marks[2]
+                    }
+                    // an apostrophe within or before a word is the same in every language
+                    '\'' if quotes && next.is_some_and(char::is_alphanumeric) => "\u{2019}",
+                    '\'' if quotes => marks[3],
+                    _ => &remaining_text[..value.len_utf8()],
+                };
+                (replacement, value.len_utf8())
+            };
+        result.push_str(replacement);
+        *previous = replacement.chars().last();
+        index += length;
+    }
+    result
+}
+
+fn format_inlines(inlines: &mut [Inline], previous: &mut Option<char>, typography: &Typography) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => *text = format_text(text, previous, typography),
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content)
+            | Inline::Link(Link { content, .. })
+            | Inline::LinkReference(LinkReference { content, .. }) => {
+                format_inlines(content, previous, typography);
+            }
+            Inline::LineBreak | Inline::SoftBreak => *previous = Some('\n'),
+            Inline::Code(_) | Inline::FootnoteReference(_) | Inline::Image(_) => {
+                *previous = Some(OBJECT_CHARACTER);
+            }
+        }
+    }
+}
+
+// joins the last two words with a non-breaking space, so the last line never holds a single word
+fn prevent_widow(inlines: &mut [Inline]) {
+    match inlines.last_mut() {
+        Some(Inline::Text(text)) => {
+            if let Some(index) = text.rfind(' ') {
+                if !text[index..].contains(['>', '}']) && !text[..index].trim().is_empty() {
+                    text.replace_range(index..=index, "&nbsp;");
+                }
+            }
+        }
+        Some(
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content)
+            | Inline::Link(Link { content, .. })
+            | Inline::LinkReference(LinkReference { content, .. }),
+        ) => prevent_widow(content),
+        _ => {}
+    }
+}
+
+fn format_prose(inlines: &mut [Inline], typography: &Typography) {
+    format_inlines(inlines, &mut None, typography);
+    if typography.rules.contains(&TypographyRule::Widows) {
+        prevent_widow(inlines);
+    }
+}
+
+/// Applies the typography rules to paragraphs and table cells, leaving headings, with their own
+/// formatting, and code unchanged
+pub fn format_typography(document: &mut Document, typography: &Typography) {
+    for block in document.blocks.iter_mut().chain(
+        document
+            .footnotes
+            .iter_mut()
+            .flat_map(|footnote| &mut footnote.children),
+    ) {
+        block.visit_blocks_mut(&mut |block| match block {
+            Block::Paragraph(content) => format_prose(content, typography),
+            Block::Table(table) => {
+                for cell in table
+                    .head
+                    .iter_mut()
+                    .chain(table.body.iter_mut())
+                    .flat_map(|row| &mut row.cells)
+                {
+                    format_prose(cell, typography);
+                }
+            }
+            _ => {}
+        });
+    }
+}
+
+/// Sets the quotation marks, and French punctuation spacing, of headings, for a locale other than
+/// English, which the renderer formats
+pub fn format_heading_typography(document: &mut Document, locale: Locale) {
+    let typography = Typography {
+        rules: vec![TypographyRule::Quotes, TypographyRule::PunctuationSpaces],
+        units: Vec::new(),
+        locale,
+    };
+    for block in &mut document.blocks {
+        block.visit_blocks_mut(&mut |block| {
+            if let Block::Heading(heading) = block {
+                format_inlines(&mut heading.content, &mut None, &typography);
+            }
+        });
+    }
+}
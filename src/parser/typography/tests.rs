@@ -0,0 +1,247 @@
+use crate::parser::{
+    ast::{Block, Inline, Link},
+    parse_document,
+    typography::{
+        format_heading_typography, format_text, format_typography, parse_dimensions,
+        parse_number_range, parse_number_unit,
+    },
+    Locale, Typography, TypographyRule,
+};
+
+fn text(value: &str) -> Inline {
+    Inline::Text(String::from(value))
+}
+
+#[test]
+pub fn test_format_text() {
+    let smart = Typography::smart();
+    let mut previous = None;
+    assert_eq!(
+        format_text("\"It's the '90s\" -- or so... she said---'twas so", &mut previous, &smart),
+        "\u{201c}It\u{2019}s the \u{2019}90s\u{201d} \u{2013} or so\u{2026} she said\u{2014}\u{2018}twas so"
+    );
+    assert_eq!(previous, Some('o'));
+
+    // a quote after a word, in an earlier inline, closes
+    let mut previous = Some('d');
+    assert_eq!(
+        format_text("' and \"", &mut previous, &smart),
+        "\u{2019} and \u{201c}"
+    );
+
+    // tags, JSX expressions and URLs are left alone
+    let mut previous = None;
+    assert_eq!(
+        format_text(
+            "<abbr title=\"Don't -- Repeat\">DRY</abbr> {props['a--b']} https://example.com/a--b...",
+            &mut previous,
+            &smart
+        ),
+        "<abbr title=\"Don't -- Repeat\">DRY</abbr> {props['a--b']} https://example.com/a--b..."
+    );
+}
+
+#[test]
+pub fn test_format_text_locales() {
+    let french = Typography {
+        locale: Locale::French,
+        ..Typography::smart()
+    };
+    let mut previous = None;
+    assert_eq!(
+        format_text(
+            "\"Bonjour\" : l'ami ! C'est 'ça' ? Quoi?! Note: 10:30 https://example.com/?a=1",
+            &mut previous,
+            &french
+        ),
+        "\u{ab}\u{202f}Bonjour\u{202f}\u{bb}\u{202f}: l\u{2019}ami\u{202f}! C\u{2019}est \u{201c}ça\u{201d}\u{202f}? Quoi\u{202f}?! Note\u{202f}: 10:30 https://example.com/?a=1"
+    );
+
+    // HTML entities are copied unchanged, and a space entity is not doubled
+    let mut previous = None;
+    assert_eq!(
+        format_text(
+            "C &amp; D : 6&times;9&nbsp;! &#169; &#x2013; \"&Eacute;t&eacute;\"",
+            &mut previous,
+            &french
+        ),
+        "C &amp; D\u{202f}: 6&times;9&nbsp;! &#169; &#x2013; \u{ab}\u{202f}&Eacute;t&eacute;\u{202f}\u{bb}"
+    );
+
+    let german = Typography {
+        locale: Locale::German,
+        ..Typography::smart()
+    };
+    let mut previous = None;
+    assert_eq!(
+        format_text("\"Hallo\", sagte er: 'ja'. Peter's", &mut previous, &german),
+        "\u{201e}Hallo\u{201c}, sagte er: \u{201a}ja\u{2018}. Peter\u{2019}s"
+    );
+
+    // punctuation is only spaced in French
+    let mut previous = None;
+    assert_eq!(
+        format_text(
+            "Why? Because: \"it's\"",
+            &mut previous,
+            &Typography::smart()
+        ),
+        "Why? Because: \u{201c}it\u{2019}s\u{201d}"
+    );
+}
+
+#[test]
+pub fn test_parse_number_rules() {
+    let units = vec![String::from("mm"), String::from("%")];
+    assert_eq!(
+        parse_dimensions("6x9 film", &units),
+        Ok(("9 film", ("6", "", "")))
+    );
+    assert_eq!(
+        parse_dimensions("60 \u{d7} 70mm", &units),
+        Ok(("70mm", ("60", " ", " ")))
+    );
+    assert!(parse_dimensions("0x1F", &units).is_err());
+    assert!(parse_dimensions("0x10 mask", &units).is_err());
+    assert!(parse_dimensions("3 x faster", &units).is_err());
+
+    assert_eq!(
+        parse_number_range("10-20 exposures", &units),
+        Ok(("20 exposures", "10"))
+    );
+    assert_eq!(parse_number_range("2.8-5.6", &units), Ok(("5.6", "2.8")));
+    assert!(parse_number_range("20-10", &units).is_err());
+    assert!(parse_number_range("2023-08-11", &units).is_err());
+    assert!(parse_number_range("10-20-30", &units).is_err());
+    assert!(parse_number_range("555-1234", &units).is_err());
+    assert!(parse_number_range("01-10", &units).is_err());
+    assert_eq!(parse_number_range("1-100", &units), Ok(("100", "1")));
+
+    assert_eq!(
+        parse_number_unit("35mm film", &units),
+        Ok((" film", ("35", "mm")))
+    );
+    assert_eq!(parse_number_unit("2.5 %", &units), Ok(("", ("2.5", "%"))));
+    assert!(parse_number_unit("35mmf", &units).is_err());
+    assert!(parse_number_unit("35  mm", &units).is_err());
+}
+
+#[test]
+pub fn test_format_text_numbers() {
+    let smart = Typography::smart();
+    let mut previous = None;
+    assert_eq!(
+        format_text(
+            "Frames from 6x6 to 60 x 170mm, 10-20 exposures and 50% off, on 2023-08-11 at 10:30-11:00 for RZ67 3.1.2-4",
+            &mut previous,
+            &smart
+        ),
+        "Frames from 6\u{d7}6 to 60 \u{d7} 170&nbsp;mm, 10&thinsp;&ndash;&thinsp;20 exposures and 50% off, on 2023-08-11 at 10:30-11:00 for RZ67 3.1.2-4"
+    );
+
+    // hexadecimal numbers and phone numbers are left alone
+    let mut previous = None;
+    assert_eq!(
+        format_text("Mask 0x10, or call 555-1234", &mut previous, &smart),
+        "Mask 0x10, or call 555-1234"
+    );
+
+    // only the chosen rules apply, with the chosen units
+    let typography = Typography {
+        rules: vec![TypographyRule::Units],
+        units: vec![String::from("frames")],
+        ..Typography::default()
+    };
+    let mut previous = None;
+    assert_eq!(
+        format_text("\"12 frames\" -- 6x9 35mm", &mut previous, &typography),
+        "\"12&nbsp;frames\" -- 6x9 35mm"
+    );
+}
+
+#[test]
+pub fn test_format_typography() {
+    let mdx = "\"Use `'a'` for *'quoted'* text\" -- see [the 'docs'](https://example.com/a--b)
+
+| It's | Dashes -- |
+| ---- | --------- |
+| '99  | Yes...    |
+
+## Don't change headings
+
+```
+'code' -- stays
+```
+";
+    let mut document = parse_document(mdx, "<input>").unwrap();
+    format_typography(&mut document, &Typography::smart());
+    assert_eq!(
+        document.blocks[0],
+        Block::Paragraph(vec![
+            text("\u{201c}Use "),
+            Inline::Code(String::from("'a'")),
+            text(" for "),
+            Inline::Emphasis(vec![text("\u{2018}quoted\u{2019}")]),
+            text(" text\u{201d} \u{2013} see "),
+            Inline::Link(Link {
+                href: String::from("https://example.com/a--b"),
+                attributes: String::from("href=\"https://example.com/a--b\""),
+                has_rel: false,
+                has_target: false,
+                content: vec![text("the&nbsp;\u{2018}docs\u{2019}")]
+            })
+        ])
+    );
+    match &document.blocks[1] {
+        Block::Table(table) => {
+            assert_eq!(table.head[0].cells[0], vec![text("It\u{2019}s")]);
+            assert_eq!(table.head[0].cells[1], vec![text("Dashes&nbsp;\u{2013}")]);
+            assert_eq!(table.body[0].cells[0], vec![text("\u{2019}99")]);
+            assert_eq!(table.body[0].cells[1], vec![text("Yes\u{2026}")]);
+        }
+        other => panic!("expected a table, got {other:?}"),
+    }
+    match &document.blocks[2] {
+        Block::Heading(heading) => assert_eq!(heading.content, vec![text("Don't change headings")]),
+        other => panic!("expected a heading, got {other:?}"),
+    }
+    match &document.blocks[3] {
+        Block::CodeBlock(code_block) => {
+            assert_eq!(code_block.lines, vec![String::from("'code' -- stays")]);
+        }
+        other => panic!("expected a code block, got {other:?}"),
+    }
+}
+
+#[test]
+pub fn test_format_heading_typography() {
+    let mdx = "## Qu'est-ce que \"MDX\" ?
+
+## C &amp; D&nbsp;: &#x2013; E
+
+Il a dit \"non\" !
+";
+    let mut document = parse_document(mdx, "<input>").unwrap();
+    format_heading_typography(&mut document, Locale::French);
+    match &document.blocks[0] {
+        Block::Heading(heading) => assert_eq!(
+            heading.content,
+            vec![text(
+                "Qu\u{2019}est-ce que \u{ab}\u{202f}MDX\u{202f}\u{bb}\u{202f}?"
+            )]
+        ),
+        other => panic!("expected a heading, got {other:?}"),
+    }
+    match &document.blocks[1] {
+        Block::Heading(heading) => {
+            assert_eq!(heading.content, vec![text("C &amp; D&nbsp;: &#x2013; E")]);
+        }
+        other => panic!("expected a heading, got {other:?}"),
+    }
+
+    // prose is left to the typography rules
+    assert_eq!(
+        document.blocks[2],
+        Block::Paragraph(vec![text("Il a dit \"non\" !")])
+    );
+}
@@ -0,0 +1,961 @@
+#[cfg(test)]
+mod tests;
+
+use crate::parser::{
+    ast::{
+        Block, BlockQuote, CalloutType, CodeBlock, Document, DocumentHeading, Footnote,
+        FootnoteDefinition, FootnoteReference, FrontmatterValue, Heading, HtmlBlock, Image, Inline,
+        JsxComponent, Link, LinkReference, List, ListType, LocalImage, Table, TableAlign,
+    },
+    error::IResult,
+    jsx::JSXComponentType,
+    parse_href_scheme,
+    typography::NUMBER_RANGE_DASH,
+};
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_until},
+    character::complete::digit1,
+    combinator::recognize,
+    sequence::{delimited, separated_pair},
+    Parser,
+};
+use std::{borrow::Cow, collections::HashSet, fmt};
+
+/// Astro markup generated from MDX source
+#[derive(Debug, PartialEq)]
+pub struct AstroOutput {
+    /// Astro frontmatter, including the `---` delimiters
+    pub frontmatter: String,
+
+    /// page markup, following the frontmatter
+    pub body: String,
+}
+
+impl fmt::Display for AstroOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.frontmatter, self.body)
+    }
+}
+
+/// Render a parsed document as an Astro page
+pub fn render_document(document: &Document, slug: &str, callout_component: &str) -> AstroOutput {
+    let mut components: HashSet<JSXComponentType> = HashSet::new();
+    collect_component_types(&document.blocks, &mut components);
+    for footnote in &document.footnotes {
+        collect_component_types(&footnote.children, &mut components);
+    }
+
+    let mut frontmatter = String::new();
+    for line in form_astro_frontmatter(
+        &components,
+        &document.frontmatter,
+        &document.headings,
+        &document.image_assets,
+        &document.declarations,
+        slug,
+        callout_component,
+    ) {
+        frontmatter.push_str(&line);
+        frontmatter.push('\n');
+    }
+    let mut body = String::new();
+    for block in &document.blocks {
+        body.push_str(&render_block(block));
+        body.push('\n');
+    }
+    if !document.footnotes.is_empty() {
+        body.push_str(&render_footnotes(&document.footnotes));
+        body.push('\n');
+    }
+    AstroOutput { frontmatter, body }
+}
+
+// components used in the document, which need importing in the page frontmatter
+fn collect_component_types(blocks: &[Block], components: &mut HashSet<JSXComponentType>) {
+    for block in blocks {
+        match block {
+            Block::BlockQuote(BlockQuote { callout, children }) => {
+                if callout.is_some() {
+                    components.insert(JSXComponentType::Callout);
+                }
+                collect_component_types(children, components);
+            }
+            Block::CodeBlock(_) => {
+                components.insert(JSXComponentType::CodeFragment);
+            }
+            Block::FootnoteDefinition(FootnoteDefinition { children, .. })
+            | Block::HtmlBlock(HtmlBlock { children, .. }) => {
+                collect_component_types(children, components);
+            }
+            Block::JsxComponent(JsxComponent {
+                component,
+                children,
+            }) => {
+                components.insert(*component);
+                collect_component_types(children, components);
+            }
+            Block::List(List { items, .. }) => {
+                for item in items {
+                    collect_component_types(&item.children, components);
+                }
+            }
+            Block::Heading(Heading { content, .. }) | Block::Paragraph(content) => {
+                collect_inline_component_types(content, components);
+            }
+            Block::Table(table) => {
+                for cell in table
+                    .head
+                    .iter()
+                    .chain(&table.body)
+                    .flat_map(|row| &row.cells)
+                {
+                    collect_inline_component_types(cell, components);
+                }
+            }
+            Block::Markup(_) | Block::ThematicBreak => {}
+        }
+    }
+}
+
+fn collect_inline_component_types(inlines: &[Inline], components: &mut HashSet<JSXComponentType>) {
+    for inline in inlines {
+        match inline {
+            Inline::Image(Image {
+                local: Some(LocalImage::PostImage(_)),
+                ..
+            }) => {
+                components.insert(JSXComponentType::Image);
+            }
+            Inline::FootnoteReference(FootnoteReference {
+                sidenote: Some(children),
+                ..
+            }) => {
+                components.insert(JSXComponentType::Sidenote);
+                collect_component_types(children, components);
+            }
+            Inline::Emphasis(content)
+            | Inline::Strikethrough(content)
+            | Inline::Strong(content)
+            | Inline::Link(Link { content, .. })
+            | Inline::LinkReference(LinkReference { content, .. }) => {
+                collect_inline_component_types(content, components);
+            }
+            Inline::Code(_)
+            | Inline::FootnoteReference(_)
+            | Inline::Image(_)
+            | Inline::LineBreak
+            | Inline::SoftBreak
+            | Inline::Text(_) => {}
+        }
+    }
+}
+
+// escapes code for a JavaScript template literal, starting with backslashes, so later escapes stay
+fn escape_code(line: &str) -> String {
+    line.replace('\\', "\\\\")
+        .replace('<', "\\u003C")
+        .replace('>', "\\u003E")
+        .replace('`', "\\u0060")
+        .replace('{', "\\u007B")
+        .replace('}', "\\u007D")
+        .replace("import.", "import..")
+        .replace("process.env", "process..env")
+}
+
+/* if the last word of the title is shorter than 6 characters, replaces the last space with a
+ * non-breaking space
+ */
+fn format_heading_widows(heading: &str) -> String {
+    match heading.rsplit_once(' ') {
+        Some((before_space, after_space)) => {
+            if after_space.len() < 5 {
+                format!(
+                    "{}\\u00a0{}",
+                    format_heading(before_space),
+                    format_heading(after_space)
+                )
+            } else {
+                format_heading(heading).to_string()
+            }
+        }
+        None => format_heading(heading).to_string(),
+    }
+}
+
+fn format_heading<'a, I: Into<Cow<'a, str>>>(heading: I) -> Cow<'a, str> {
+    fn is_replace_character(c: char) -> bool {
+        c == '-' || c == '\'' || c == '"'
+    }
+
+    let heading = heading.into();
+    let first = heading.find(is_replace_character);
+    if let Some(first) = first {
+        let (mut result, rest) = match first {
+            0 => match &heading[0..1] {
+                "\"" => (String::from("\\u201c"), heading[1..].chars()),
+                "'" => (String::from("\\u2018"), heading[1..].chars()),
+                _ => (String::from(&heading[0..first]), heading[first..].chars()),
+            },
+            _ => {
+                if &heading[(first - 1)..first] == " " {
+                    (
+                        String::from(&heading[0..(first - 1)]),
+                        heading[(first - 1)..].chars(),
+                    )
+                } else {
+                    (String::from(&heading[0..first]), heading[first..].chars())
+                }
+            }
+        };
+        result.reserve(heading.len() - first);
+
+        let mut preceded_by_space = false;
+        for c in rest {
+            match c {
+                '-' => result.push_str("&#x2011;"), // non-breaking hyphen
+                ' ' => {
+                    preceded_by_space = true;
+                    result.push(c);
+                }
+                '\'' => {
+                    if preceded_by_space {
+                        preceded_by_space = false;
+                        result.push_str("\\u2018");
+                    } else {
+                        result.push_str("\\u2019");
+                    }
+                }
+                '"' => {
+                    if preceded_by_space {
+                        preceded_by_space = false;
+                        result.push_str("\\u201c");
+                    } else {
+                        result.push_str("\\u201d");
+                    }
+                }
+                _ => {
+                    preceded_by_space = false;
+                    result.push(c);
+                }
+            }
+        }
+        Cow::Owned(result)
+    } else {
+        heading
+    }
+}
+
+// JavaScript string literal, quoted with single quotes
+fn render_string_literal(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('\'');
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\'' => result.push_str("\\'"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result.push('\'');
+    result
+}
+
+// object keys which are not valid identifiers are quoted
+fn render_object_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        render_string_literal(key)
+    }
+}
+
+fn render_object_fields(fields: &[(String, FrontmatterValue)], indentation: usize) -> String {
+    if fields.is_empty() {
+        return String::from("{}");
+    }
+    let padding = " ".repeat(indentation);
+    let mut markup = String::from("{");
+    for (key, value) in fields {
+        markup.push_str(&format!(
+            "\n{padding}  {}: {},",
+            render_object_key(key),
+            render_frontmatter_value(value, indentation + 2)
+        ));
+    }
+    markup.push_str(&format!("\n{padding}}}"));
+    markup
+}
+
+fn render_frontmatter_value(value: &FrontmatterValue, indentation: usize) -> String {
+    match value {
+        FrontmatterValue::Boolean(value) => value.to_string(),
+        FrontmatterValue::Integer(value) => value.to_string(),
+        FrontmatterValue::List(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(|value| render_frontmatter_value(value, indentation))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        FrontmatterValue::Map(fields) => render_object_fields(fields, indentation),
+        FrontmatterValue::Null => String::from("null"),
+        FrontmatterValue::Real(value) => match value.parse::<f64>() {
+            Ok(number) if number.is_finite() => value.to_string(),
+            _ => render_string_literal(value),
+        },
+        FrontmatterValue::String(value) => render_string_literal(value),
+    }
+}
+
+fn form_astro_frontmatter(
+    components: &HashSet<JSXComponentType>,
+    frontmatter: &[(String, FrontmatterValue)],
+    headings: &[DocumentHeading],
+    image_assets: &[String],
+    declarations: &[String],
+    slug: &str,
+    callout_component: &str,
+) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut define_slug = false;
+    let mut image_data_imports: Vec<String> = Vec::new();
+
+    result.push(String::from("---"));
+    if components.contains(&JSXComponentType::Callout) {
+        result.push(format!("import Callout from '{callout_component}';"));
+    }
+    if components.contains(&JSXComponentType::CodeFragment) {
+        result.push(String::from(
+            "import CodeFragment from '~components/CodeFragment.svelte';",
+        ));
+    }
+    result.push(String::from(
+        "import Heading from '~components/Heading.svelte';",
+    ));
+    if components.contains(&JSXComponentType::HowTo) {
+        define_slug = true;
+        result.push(String::from(
+            "import HowTo from '~components/HowTo/index.svelte';
+import HowToSection from '~components/HowTo/HowToSection.svelte';
+import HowToStep from '~components/HowTo/HowToStep.svelte';
+import HowToDirection from '~components/HowTo/HowToDirection.svelte';",
+        ));
+    }
+    if components.contains(&JSXComponentType::GatsbyNotMaintained) {
+        result.push(String::from(
+            "import GatsbyNotMaintained from '~components/BlogPost/GatsbyNotMaintained.svelte';",
+        ));
+    }
+    if !image_assets.is_empty() {
+        result.push(String::from(
+            "import { Image as AstroImage } from 'astro:assets';",
+        ));
+        for (index, src) in image_assets.iter().enumerate() {
+            result.push(format!("import image{index} from '{src}';"));
+        }
+    }
+    if components.contains(&JSXComponentType::Image)
+        || components.contains(&JSXComponentType::Video)
+        || components.contains(&JSXComponentType::Tweet)
+    {
+        result.push(String::from("import { getEntry } from 'astro:content';"));
+    }
+    if components.contains(&JSXComponentType::Image) {
+        define_slug = true;
+        image_data_imports.push(String::from("images"));
+        result.push(String::from(
+            "import Image from '~components/BlogPost/Image.svelte';",
+        ));
+    }
+    result.push(String::from(
+        "import LinkIcon from '~components/Icons/Link.svelte';
+import InlineCodeFragment from '~components/InlineCodeFragment.svelte';",
+    ));
+    if components.contains(&JSXComponentType::Poll) {
+        define_slug = true;
+        result.push(String::from("import Poll from '~components/Poll.svelte';"));
+    }
+    if components.contains(&JSXComponentType::Image) {
+        result.push(String::from(
+            "import type { NebulaPicture, PostPagePictures } from '~types/image';",
+        ));
+    } else if components.contains(&JSXComponentType::Video) {
+        result.push(String::from(
+            "import type { PostPagePictures } from '~types/image';",
+        ));
+    }
+    if components.contains(&JSXComponentType::Questions) {
+        result.push(String::from(
+            "import Questions from '~components/Questions.svelte';",
+        ));
+        result.push(format!(
+            "import questions from '~content-raw/blog/{slug}/questions.json';"
+        ));
+    }
+    if components.contains(&JSXComponentType::Sidenote) {
+        result.push(String::from(
+            "import Sidenote from '~components/Sidenote.svelte';",
+        ));
+    }
+    if components.contains(&JSXComponentType::Tweet) {
+        result.push(String::from(
+            "import Tweet from '~components/Tweet.svelte';",
+        ));
+    }
+    result.push(String::from(
+        "import TwitterMessageLink from '~components/Link/TwitterMessageLink.svelte';",
+    ));
+    if components.contains(&JSXComponentType::Video) {
+        define_slug = true;
+        image_data_imports.push(String::from("poster"));
+        result.push(String::from(
+            "import Video from '~components/Video.svelte';",
+        ));
+    }
+    if define_slug {
+        result.push("import website from '~configuration/website';".to_string());
+        result.push("\nconst { newsletterUrl } = website;".to_string());
+        result.push(format!("const slug = '{slug}';"));
+        if components.contains(&JSXComponentType::Image)
+            && components.contains(&JSXComponentType::Video)
+        {
+            result.push(
+                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
+const {
+  data: { pagePictures, pictures },
+}: { data: { pagePictures: PostPagePictures; pictures: NebulaPicture[] } } =
+  postImagesContentCollectionEntry;
+const {
+  poster: { src: poster },
+} = pagePictures;"
+                    .to_string(),
+            );
+            result.push(
+                "const imageProps = pictures.map((element, index) => ({
+  index,
+  ...element,
+  slug,
+}));"
+                    .to_string(),
+            );
+        } else if components.contains(&JSXComponentType::Image) {
+            result.push(
+                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
+const {
+  data: { pictures },
+}: { data: { pictures: NebulaPicture[] } } = postImagesContentCollectionEntry;"
+                    .to_string(),
+            );
+            result.push(
+                "const imageProps = pictures.map((element, index) => ({
+  index,
+  ...element,
+  slug,
+}));"
+                    .to_string(),
+            );
+        } else if components.contains(&JSXComponentType::Video) {
+            result.push(
+                "const postImagesContentCollectionEntry = await getEntry('post-images', slug);
+const {
+  data: { pagePictures },
+}: { data: { pagePictures: PostPagePictures; } } =
+  postImagesContentCollectionEntry;
+const {
+  poster: { src: poster },
+} = pagePictures;"
+                    .to_string(),
+            );
+        }
+    } else {
+        result.push("import website from '~configuration/website';".to_string());
+        result.push("\nconst { newsletterUrl } = website;".to_string());
+    }
+    if components.contains(&JSXComponentType::Tweet) {
+        result.push(
+            "const pageImagesContentCollectionEntry = await getEntry('page-images', 'blog');
+const {
+  data: { pagePictures: blogPagePictures },
+}: { data: { pagePictures: PostPagePictures } } = pageImagesContentCollectionEntry;
+const {
+  twitterAvatar: { src: avatarSrc, placeholder: avatarPlaceholder },
+} = blogPagePictures;"
+                .to_string(),
+        );
+    }
+    if !frontmatter.is_empty() {
+        result.push(format!(
+            "const frontmatter = {} as const;",
+            render_object_fields(frontmatter, 0)
+        ));
+    }
+    if !headings.is_empty() {
+        result.push(format!(
+            "export const headings = [\n{}];",
+            headings
+                .iter()
+                .map(|DocumentHeading { depth, slug, text }| format!(
+                    "  {{ depth: {depth}, slug: {}, text: {} }},\n",
+                    render_string_literal(slug),
+                    render_string_literal(text)
+                ))
+                .collect::<String>()
+        ));
+    }
+    for line in declarations {
+        result.push(line.to_string());
+    }
+    result.push(String::from("---\n"));
+    result
+}
+
+fn render_block(block: &Block) -> String {
+    match block {
+        Block::BlockQuote(block_quote) => render_block_quote(block_quote),
+        Block::CodeBlock(code_block) => render_code_block(code_block),
+        // definitions are moved to the document footnotes, so none are left to render
+        Block::FootnoteDefinition(_) => String::new(),
+        Block::Heading(heading) => render_heading(heading),
+        Block::JsxComponent(JsxComponent {
+            component: JSXComponentType::TableOfContents,
+            children,
+        }) => format!(
+            "<nav class=\"table-of-contents\" aria-label=\"Table of contents\">\n{}\n</nav>",
+            children
+                .iter()
+                .map(render_block)
+                .collect::<Vec<String>>()
+                .join("\n")
+        ),
+        Block::HtmlBlock(HtmlBlock { children, .. })
+        | Block::JsxComponent(JsxComponent { children, .. }) => children
+            .iter()
+            .map(render_block)
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Block::List(list) => render_list(list, 0),
+        Block::Markup(markup) => markup.to_string(),
+        Block::Paragraph(content) => render_paragraph(content),
+        Block::Table(table) => render_table(table),
+        Block::ThematicBreak => String::from("<hr />"),
+    }
+}
+
+// callouts render as a `Callout` component, with the callout type as a prop
+fn render_block_quote(block_quote: &BlockQuote) -> String {
+    let (opening_tag, closing_tag) = match block_quote.callout {
+        Some(callout) => {
+            let callout_type = match callout {
+                CalloutType::Caution => "caution",
+                CalloutType::Important => "important",
+                CalloutType::Note => "note",
+                CalloutType::Tip => "tip",
+                CalloutType::Warning => "warning",
+            };
+            (format!("<Callout type=\"{callout_type}\">"), "</Callout>")
+        }
+        None => (String::from("<blockquote>"), "</blockquote>"),
+    };
+    let mut markup = opening_tag;
+    for child in &block_quote.children {
+        markup.push('\n');
+        markup.push_str(&render_block(child));
+    }
+    markup.push('\n');
+    markup.push_str(closing_tag);
+    markup
+}
+
+fn render_code_block(code_block: &CodeBlock) -> String {
+    let mut markup = String::from("<CodeFragment\n  client:visible");
+    if let Some(value) = &code_block.language {
+        markup.push_str("\n  language=\"");
+        markup.push_str(value);
+        markup.push('\"');
+    };
+    if let Some(value) = &code_block.first_line_number {
+        markup.push_str("\n  firstLine={");
+        markup.push_str(value);
+        markup.push('}');
+    };
+    if let Some(value) = &code_block.highlight_line_numbers {
+        markup.push_str("\n  highlightLines={`");
+        markup.push_str(value);
+        markup.push_str("`}");
+    };
+    if let Some(value) = &code_block.title {
+        markup.push_str("\n  title=\"");
+        markup.push_str(value);
+        markup.push('\"');
+    };
+    if let Some(value) = &code_block.caption {
+        markup.push_str("\n  caption=\"");
+        markup.push_str(value);
+        markup.push('\"');
+    };
+    if code_block.collapse {
+        markup.push_str("\n  collapse");
+    };
+    markup.push_str("\n  code={`");
+    for line in &code_block.lines {
+        markup.push('\n');
+        markup.push_str(&escape_code(line));
+    }
+    markup.push_str("\n  `} />");
+    markup
+}
+
+fn render_heading(heading: &Heading) -> String {
+    let Heading {
+        level,
+        id,
+        classes,
+        content,
+        ..
+    } = heading;
+    let text: String = content
+        .iter()
+        .map(|inline| match inline {
+            Inline::Code(code) => format!("<code>{code}</code>"),
+            other => render_inline(other),
+        })
+        .collect();
+    let class_attribute = if classes.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{}\"", classes.join(" "))
+    };
+    format!(
+        "<h{level} id=\"{id}\"{class_attribute}><Heading client:visible id=\"{id}\" text=\"{}\"/></h{level}>",
+        format_heading_widows(text.trim_end())
+    )
+}
+
+// disabled checkbox, labelled by the item text, so screen readers announce the task with its state
+fn render_task_list_item(content: &[Inline], checked: bool) -> String {
+    let checked_attribute = if checked { " checked" } else { "" };
+    format!(
+        "<label><input type=\"checkbox\" disabled{checked_attribute} /> {}</label>",
+        render_inlines(content)
+    )
+}
+
+// nested lists are indented by a further four spaces
+fn render_list(list: &List, indentation: usize) -> String {
+    let padding = " ".repeat(indentation);
+    let (tag_name, opening_tag) = match (list.list_type, list.start.as_deref()) {
+        (ListType::Ordered, None | Some("1")) => ("ol", String::from("<ol>")),
+        (ListType::Ordered, Some(start)) => ("ol", format!("<ol start=\"{start}\">")),
+        (ListType::Unordered, _) => ("ul", String::from("<ul>")),
+    };
+    let mut markup = format!("{padding}{opening_tag}");
+    for item in &list.items {
+        markup.push_str(&format!("\n{padding}  <li>"));
+        let mut closing_padding = "";
+        for (index, child) in item.children.iter().enumerate() {
+            match child {
+                // paragraphs of tight lists are not wrapped in `<p>`
+                Block::Paragraph(content) if index == 0 && !list.loose => match item.checked {
+                    Some(checked) => markup.push_str(&render_task_list_item(content, checked)),
+                    None => markup.push_str(&render_inlines(content)),
+                },
+                Block::Paragraph(content) if !list.loose => {
+                    markup.push('\n');
+                    markup.push_str(&render_inlines(content));
+                    closing_padding = "  ";
+                }
+                Block::Paragraph(content) if index == 0 && item.checked.is_some() => {
+                    let checked = item.checked == Some(true);
+                    markup.push_str(&format!(
+                        "\n<p>{}</p>",
+                        render_task_list_item(content, checked)
+                    ));
+                    closing_padding = "  ";
+                }
+                Block::List(nested_list) => {
+                    markup.push('\n');
+                    markup.push_str(&render_list(nested_list, indentation + 4));
+                    closing_padding = "  ";
+                }
+                other => {
+                    markup.push('\n');
+                    markup.push_str(&render_block(other));
+                    closing_padding = "  ";
+                }
+            }
+        }
+        if !closing_padding.is_empty() {
+            markup.push_str(&format!("\n{padding}{closing_padding}"));
+        }
+        markup.push_str("</li>");
+    }
+    markup.push_str(&format!("\n{padding}</{tag_name}>"));
+    markup
+}
+
+fn render_paragraph(content: &[Inline]) -> String {
+    // an image on its own is not wrapped in a paragraph
+    if let [Inline::Image(image)] = content {
+        return render_image(image);
+    }
+    let markup = render_inlines(content);
+    let markup = if let Ok((_, value)) = format_inline_wrap_text_number_range(&markup) {
+        value
+    } else {
+        markup
+    };
+    format!("<p>{markup}</p>")
+}
+
+// style attribute for the column, from the alignment markers in the table delimiter row
+fn render_table_cell_alignment(table: &Table, column: usize) -> &'static str {
+    match table.alignments.get(column).copied().flatten() {
+        Some(TableAlign::Centre) => " style=\"text-align: center\"",
+        Some(TableAlign::Left) => " style=\"text-align: left\"",
+        Some(TableAlign::Right) => " style=\"text-align: right\"",
+        None => "",
+    }
+}
+
+fn render_table(table: &Table) -> String {
+    let mut markup = String::from("<table>\n  <thead>");
+    for row in &table.head {
+        markup.push_str("\n    <tr>");
+        for (column, cell) in row.cells.iter().enumerate() {
+            markup.push_str("\n      <th scope=\"col\"");
+            markup.push_str(render_table_cell_alignment(table, column));
+            markup.push('>');
+            markup.push_str(&render_inlines(cell));
+            markup.push_str("</th>");
+        }
+        markup.push_str("\n    </tr>");
+    }
+    markup.push_str("\n  </thead>\n  <tbody>");
+    for row in &table.body {
+        markup.push_str("\n    <tr>");
+        for (column, cell) in row.cells.iter().enumerate() {
+            markup.push_str("\n      <td");
+            markup.push_str(render_table_cell_alignment(table, column));
+            markup.push('>');
+            markup.push_str(&render_inlines(cell));
+            markup.push_str("</td>");
+        }
+        markup.push_str("\n    </tr>");
+    }
+    markup.push_str("\n  </tbody>\n</table>");
+    markup
+}
+
+fn render_inlines(inlines: &[Inline]) -> String {
+    inlines.iter().map(render_inline).collect()
+}
+
+fn render_inline(inline: &Inline) -> String {
+    match inline {
+        Inline::Code(code) => format!("<InlineCodeFragment code={{`{}`}} />", escape_code(code)),
+        Inline::Emphasis(content) => format!("<em>{}</em>", render_inlines(content)),
+        Inline::FootnoteReference(reference) => render_footnote_reference(reference),
+        Inline::Image(image) => render_image(image),
+        Inline::LineBreak => String::from("<br />\n"),
+        Inline::Link(link) => render_link(link),
+        Inline::LinkReference(reference) => format!("[{}]", render_inlines(&reference.content)),
+        Inline::SoftBreak => String::from("\n"),
+        Inline::Strikethrough(content) => format!("<del>{}</del>", render_inlines(content)),
+        Inline::Strong(content) => format!("<strong>{}</strong>", render_inlines(content)),
+        Inline::Text(text) => text.to_string(),
+    }
+}
+
+// id of the reference, which the footnote back-reference links to
+fn footnote_reference_id(number: usize, occurrence: usize) -> String {
+    if occurrence == 1 {
+        format!("fnref-{number}")
+    } else {
+        format!("fnref-{number}-{occurrence}")
+    }
+}
+
+fn render_footnote_reference(reference: &FootnoteReference) -> String {
+    let FootnoteReference {
+        number,
+        occurrence,
+        sidenote,
+    } = reference;
+    match sidenote {
+        /* paragraphs are rendered without their wrappers, and separated by line breaks, so the
+         * sidenote can sit inside the referencing paragraph
+         */
+        Some(children) => {
+            let content = children
+                .iter()
+                .map(|block| match block {
+                    Block::Paragraph(content) => render_inlines(content),
+                    other => render_block(other),
+                })
+                .collect::<Vec<String>>()
+                .join("<br />");
+            format!("<Sidenote number={{{number}}}>{content}</Sidenote>")
+        }
+        None => format!(
+            "<sup><a href=\"#fn-{number}\" id=\"{}\" aria-describedby=\"footnote-label\">{number}</a></sup>",
+            footnote_reference_id(*number, *occurrence)
+        ),
+    }
+}
+
+// back-reference links are added to the end of the last paragraph, where there is one
+fn render_footnotes(footnotes: &[Footnote]) -> String {
+    let mut markup = String::from(
+        "<section class=\"footnotes\" aria-labelledby=\"footnote-label\">
+<h2 id=\"footnote-label\" class=\"sr-only\">Footnotes</h2>
+<ol>",
+    );
+    for (index, footnote) in footnotes.iter().enumerate() {
+        let number = index + 1;
+        let back_references = (1..=footnote.references)
+            .map(|occurrence| {
+                let label = if occurrence == 1 {
+                    format!("Back to reference {number}")
+                } else {
+                    format!("Back to reference {number}-{occurrence}")
+                };
+                format!(
+                    " <a href=\"#{}\" class=\"footnote-back-reference\" aria-label=\"{label}\">↩</a>",
+                    footnote_reference_id(number, occurrence)
+                )
+            })
+            .collect::<String>();
+        markup.push_str(&format!("\n  <li id=\"fn-{number}\">"));
+        let last_paragraph = footnote
+            .children
+            .iter()
+            .rposition(|child| matches!(child, Block::Paragraph(_)));
+        for (child_index, child) in footnote.children.iter().enumerate() {
+            markup.push('\n');
+            match child {
+                Block::Paragraph(content) if Some(child_index) == last_paragraph => {
+                    markup.push_str(&format!(
+                        "<p>{}{back_references}</p>",
+                        render_inlines(content)
+                    ));
+                }
+                other => markup.push_str(&render_block(other)),
+            }
+        }
+        if last_paragraph.is_none() {
+            markup.push_str(&format!("\n<p>{}</p>", back_references.trim_start()));
+        }
+        markup.push_str("\n  </li>");
+    }
+    markup.push_str("\n</ol>\n</section>");
+    markup
+}
+
+fn render_image(image: &Image) -> String {
+    let alt = image.alt.replace('"', "&quot;");
+    let title = match &image.title {
+        Some(value) => format!(" title=\"{}\"", value.replace('"', "&quot;")),
+        None => String::new(),
+    };
+    match image.local {
+        Some(LocalImage::AstroAsset(index)) => {
+            format!("<AstroImage src={{image{index}}} alt=\"{alt}\"{title} />")
+        }
+        Some(LocalImage::PostImage(index)) => {
+            format!("<Image index={{{index}}} alt=\"{alt}\"{title} />")
+        }
+        None => format!(
+            "<img src=\"{}\" alt=\"{alt}\"{title} loading=\"lazy\" decoding=\"async\" />",
+            image.src
+        ),
+    }
+}
+
+fn render_link(link: &Link) -> String {
+    let external_site = parse_href_scheme(&link.href).is_ok();
+    let mut additional_attributes = String::new();
+
+    if external_site {
+        if !link.has_target {
+            additional_attributes.push_str(" target=\"_blank\"");
+        }
+        if !link.has_rel {
+            additional_attributes.push_str(" rel=\"nofollow noopener noreferrer\"");
+        }
+    }
+    let icon = if external_site {
+        "&nbsp;<LinkIcon />"
+    } else {
+        ""
+    };
+    format!(
+        "<a {}{additional_attributes}>{}{icon}</a>",
+        link.attributes,
+        render_inlines(&link.content)
+    )
+}
+
+fn form_inline_wrap_text_number_range(line: &str) -> IResult<&str, String> {
+    let (remaining_line, first_tag) = recognize(separated_pair(
+        tag("<InlineCodeFragment code={`"),
+        digit1,
+        tag("`} />"),
+    ))
+    .parse(line)?;
+    let (remaining_line, _) = alt((tag("&ndash;"), tag("-"))).parse(remaining_line)?;
+    let (remaining_line, second_tag) = recognize(separated_pair(
+        tag("<InlineCodeFragment code={`"),
+        digit1,
+        tag("`} />"),
+    ))
+    .parse(remaining_line)?;
+
+    Ok((
+        remaining_line,
+        format!("{first_tag}{NUMBER_RANGE_DASH}{second_tag}"),
+    ))
+}
+
+fn form_inline_wrap_inline_code_fragment(line: &str) -> IResult<&str, String> {
+    let (remaining_line, tag_content) = delimited(
+        tag("<InlineCodeFragment code={`"),
+        take_until("/>"),
+        tag("/>"),
+    )
+    .parse(line)?;
+
+    Ok((
+        remaining_line,
+        format!("<InlineCodeFragment code={{`{tag_content}/>"),
+    ))
+}
+
+fn format_inline_wrap_text_number_range(line: &str) -> IResult<&str, String> {
+    let (remaining_line, initial_part) = take_until("<InlineCodeFragment")(line)?;
+    let (remaining_line, inline_code_fragment_part) = alt((
+        form_inline_wrap_text_number_range,
+        form_inline_wrap_inline_code_fragment,
+    ))
+    .parse(remaining_line)?;
+
+    match format_inline_wrap_text_number_range(remaining_line) {
+        Ok((_, value)) => Ok((
+            "",
+            format!("{initial_part}{inline_code_fragment_part}{value}"),
+        )),
+        Err(_) => Ok((
+            "",
+            format!("{initial_part}{inline_code_fragment_part}{remaining_line}"),
+        )),
+    }
+}
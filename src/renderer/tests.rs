@@ -0,0 +1,550 @@
+use crate::{
+    parser::{
+        ast::{
+            Block, BlockQuote, CalloutType, CodeBlock, Footnote, FootnoteDefinition,
+            FootnoteReference, FrontmatterValue, Heading, Inline, JsxComponent, Link, List,
+            ListItem, ListType, Table, TableAlign, TableRow,
+        },
+        jsx::JSXComponentType,
+    },
+    renderer::{
+        collect_component_types, escape_code, format_heading, format_heading_widows,
+        format_inline_wrap_text_number_range, render_block, render_footnotes,
+        render_frontmatter_value, render_inlines,
+    },
+};
+use std::collections::HashSet;
+
+fn text(value: &str) -> Inline {
+    Inline::Text(String::from(value))
+}
+
+#[test]
+pub fn test_collect_component_types() {
+    let blocks = vec![Block::List(List {
+        list_type: ListType::Unordered,
+        start: None,
+        loose: false,
+        items: vec![ListItem {
+            checked: None,
+            children: vec![Block::CodeBlock(CodeBlock::default())],
+        }],
+    })];
+    let mut components = HashSet::new();
+    collect_component_types(&blocks, &mut components);
+    assert_eq!(components, HashSet::from([JSXComponentType::CodeFragment]));
+}
+
+#[test]
+pub fn test_escape_code() {
+    let mdx_line = "`${variable}`";
+    assert_eq!(
+        escape_code(mdx_line),
+        "\\u0060$\\u007Bvariable\\u007D\\u0060"
+    );
+
+    // backslashes cannot escape the closing backtick, or start an escape sequence
+    assert_eq!(escape_code("a\\"), "a\\\\");
+    assert_eq!(escape_code("\\n\\u0060`"), "\\\\n\\\\u0060\\u0060");
+    assert_eq!(
+        render_inlines(&[Inline::Code(String::from("a\\"))]),
+        "<InlineCodeFragment code={`a\\\\`} />"
+    );
+}
+
+#[test]
+pub fn test_format_heading() {
+    let title = "Introducing the new cat";
+    assert_eq!(format_heading(title), "Introducing the new cat");
+
+    let title = "Introducing the zoo's new elephant";
+    assert_eq!(
+        format_heading(title),
+        "Introducing the zoo\\u2019s new elephant"
+    );
+
+    let title = "\"Introducing\" the zoo's new elephant";
+    assert_eq!(
+        format_heading(title),
+        "\\u201cIntroducing\\u201d the zoo\\u2019s new elephant"
+    );
+
+    let title = "It's not Right, but it's OK";
+    assert_eq!(
+        format_heading(title),
+        "It\\u2019s not Right, but it\\u2019s OK"
+    );
+}
+
+#[test]
+pub fn test_format_heading_widows() {
+    let title = "Introducing the new cat";
+    assert_eq!(
+        format_heading_widows(title),
+        "Introducing the new\\u00a0cat"
+    );
+
+    let title = "Introducing the zoo's new elephant";
+    assert_eq!(
+        format_heading_widows(title),
+        "Introducing the zoo\\u2019s new elephant"
+    );
+
+    let title = "\"Introducing\" the zoo's new elephant";
+    assert_eq!(
+        format_heading_widows(title),
+        "\\u201cIntroducing\\u201d the zoo\\u2019s new elephant"
+    );
+}
+
+#[test]
+pub fn test_format_inline_wrap_text_number_range() {
+    let line = "<InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} />";
+    assert_eq!(
+        format_inline_wrap_text_number_range(line),
+        Ok(("", String::from("<InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} />")))
+    );
+
+    let line =
+        "<InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+    assert_eq!(
+        format_inline_wrap_text_number_range(line),
+        Ok(("", String::from("<InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+    );
+
+    let line =
+        "Interestingly, <InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+    assert_eq!(
+        format_inline_wrap_text_number_range(line),
+        Ok(("", String::from("Interestingly, <InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+    );
+    let line =
+        "Interestingly <InlineCodeFragment code={`x`} /> and <InlineCodeFragment code={`1`} />&ndash;<InlineCodeFragment code={`3`} /> is in fact";
+    assert_eq!(
+        format_inline_wrap_text_number_range(line),
+        Ok(("", String::from("Interestingly <InlineCodeFragment code={`x`} /> and <InlineCodeFragment code={`1`} />&thinsp;&ndash;&thinsp;<InlineCodeFragment code={`3`} /> is in fact")))
+    );
+}
+
+#[test]
+pub fn test_render_block_quote() {
+    let block_quote = Block::BlockQuote(BlockQuote {
+        callout: Some(CalloutType::Warning),
+        children: vec![Block::BlockQuote(BlockQuote {
+            callout: None,
+            children: vec![Block::Paragraph(vec![text("Mind the light leaks.")])],
+        })],
+    });
+    assert_eq!(
+        render_block(&block_quote),
+        "<Callout type=\"warning\">
+<blockquote>
+<p>Mind the light leaks.</p>
+</blockquote>
+</Callout>"
+    );
+}
+
+#[test]
+pub fn test_render_code_block() {
+    let code_block = Block::CodeBlock(CodeBlock {
+        language: Some(String::from("plaintext")),
+        first_line_number: Some(String::from("2")),
+        highlight_line_numbers: Some(String::from("{5,7}")),
+        title: Some(String::from(".env")),
+        collapse: true,
+        lines: vec![String::from("PORT={port}")],
+        ..Default::default()
+    });
+    assert_eq!(
+        render_block(&code_block),
+        "<CodeFragment\n  client:visible\n  language=\"plaintext\"\n  firstLine={2}\n  highlightLines={`{5,7}`}\n  title=\".env\"\n  collapse\n  code={`\nPORT=\\u007Bport\\u007D\n  `} />"
+    );
+}
+
+#[test]
+pub fn test_render_heading() {
+    let heading = Block::Heading(Heading {
+        level: 2,
+        id: String::from("using-cargo"),
+        classes: Vec::new(),
+        custom_id: false,
+        content: vec![text("Using "), Inline::Code(String::from("cargo"))],
+    });
+    assert_eq!(
+        render_block(&heading),
+        "<h2 id=\"using-cargo\"><Heading client:visible id=\"using-cargo\" text=\"Using <code>cargo</code>\"/></h2>"
+    );
+
+    let heading = Block::Heading(Heading {
+        level: 2,
+        id: String::from("feedback-form"),
+        classes: vec![String::from("wide"), String::from("centred")],
+        custom_id: false,
+        content: vec![text("Feedback")],
+    });
+    assert_eq!(
+        render_block(&heading),
+        "<h2 id=\"feedback-form\" class=\"wide centred\"><Heading client:visible id=\"feedback-form\" text=\"Feedback\"/></h2>"
+    );
+}
+
+#[test]
+pub fn test_render_inlines() {
+    // adds rel and target attributes for external sites when they are not already there
+    let inlines = vec![Inline::Link(Link {
+        href: String::from("https://www.example.com"),
+        attributes: String::from("href=\"https://www.example.com\""),
+        has_rel: false,
+        has_target: false,
+        content: vec![text("site")],
+    })];
+    assert_eq!(
+        render_inlines(&inlines),
+        "<a href=\"https://www.example.com\" target=\"_blank\" rel=\"nofollow noopener noreferrer\">site&nbsp;<LinkIcon /></a>"
+    );
+
+    // does not add rel and target attributes to non external sites
+    let inlines = vec![Inline::Link(Link {
+        href: String::from("/home/contact-us"),
+        attributes: String::from("href=\"/home/contact-us\""),
+        has_rel: false,
+        has_target: false,
+        content: vec![text("site")],
+    })];
+    assert_eq!(
+        render_inlines(&inlines),
+        "<a href=\"/home/contact-us\">site</a>"
+    );
+
+    let inlines = vec![
+        text("To me "),
+        Inline::Code(String::from("E=mc^2")),
+        text(" is "),
+        Inline::Strong(vec![text("the")]),
+        text(" most "),
+        Inline::Emphasis(vec![text("important")]),
+        text(" equation."),
+    ];
+    assert_eq!(
+        render_inlines(&inlines),
+        "To me <InlineCodeFragment code={`E=mc^2`} /> is <strong>the</strong> most <em>important</em> equation."
+    );
+
+    let inlines = vec![
+        text("First line"),
+        Inline::SoftBreak,
+        text("second line"),
+        Inline::LineBreak,
+        text("third line"),
+    ];
+    assert_eq!(
+        render_inlines(&inlines),
+        "First line\nsecond line<br />\nthird line"
+    );
+
+    let inlines = vec![
+        text("Film is "),
+        Inline::Strikethrough(vec![text("cheap")]),
+        text(" pricey"),
+    ];
+    assert_eq!(render_inlines(&inlines), "Film is <del>cheap</del> pricey");
+}
+
+#[test]
+pub fn test_render_list() {
+    let item = |value: &str| ListItem {
+        checked: None,
+        children: vec![Block::Paragraph(vec![text(value)])],
+    };
+    let list = Block::List(List {
+        list_type: ListType::Ordered,
+        start: Some(String::from("3")),
+        loose: false,
+        items: vec![
+            item("Agfa Isolette"),
+            ListItem {
+                checked: None,
+                children: vec![
+                    Block::Paragraph(vec![text("Mamiya 6")]),
+                    Block::List(List {
+                        list_type: ListType::Unordered,
+                        start: None,
+                        loose: false,
+                        items: vec![item("75 mm lens")],
+                    }),
+                ],
+            },
+        ],
+    });
+    assert_eq!(
+        render_block(&list),
+        "<ol start=\"3\">
+  <li>Agfa Isolette</li>
+  <li>Mamiya 6
+    <ul>
+      <li>75 mm lens</li>
+    </ul>
+  </li>
+</ol>"
+    );
+}
+
+#[test]
+pub fn test_render_thematic_break() {
+    assert_eq!(render_block(&Block::ThematicBreak), "<hr />");
+}
+
+#[test]
+pub fn test_render_table_of_contents() {
+    let table_of_contents = Block::JsxComponent(JsxComponent {
+        component: JSXComponentType::TableOfContents,
+        children: vec![Block::List(List {
+            list_type: ListType::Unordered,
+            start: None,
+            loose: false,
+            items: vec![ListItem {
+                checked: None,
+                children: vec![Block::Paragraph(vec![Inline::Link(Link {
+                    href: String::from("#feedback"),
+                    attributes: String::from("href=\"#feedback\""),
+                    has_rel: false,
+                    has_target: false,
+                    content: vec![text("Feedback")],
+                })])],
+            }],
+        })],
+    });
+    assert_eq!(
+        render_block(&table_of_contents),
+        "<nav class=\"table-of-contents\" aria-label=\"Table of contents\">
+<ul>
+  <li><a href=\"#feedback\">Feedback</a></li>
+</ul>
+</nav>"
+    );
+}
+
+#[test]
+pub fn test_render_loose_list() {
+    let list = Block::List(List {
+        list_type: ListType::Unordered,
+        start: None,
+        loose: true,
+        items: vec![
+            ListItem {
+                checked: None,
+                children: vec![Block::Paragraph(vec![text("Agfa Isolette")])],
+            },
+            ListItem {
+                checked: None,
+                children: vec![
+                    Block::Paragraph(vec![text("Mamiya 6")]),
+                    Block::Paragraph(vec![text("Medium format")]),
+                ],
+            },
+        ],
+    });
+    assert_eq!(
+        render_block(&list),
+        "<ul>
+  <li>
+<p>Agfa Isolette</p>
+  </li>
+  <li>
+<p>Mamiya 6</p>
+<p>Medium format</p>
+  </li>
+</ul>"
+    );
+}
+
+#[test]
+pub fn test_render_task_list() {
+    let item = |value: &str, checked: bool| ListItem {
+        checked: Some(checked),
+        children: vec![Block::Paragraph(vec![text(value)])],
+    };
+    let list = Block::List(List {
+        list_type: ListType::Unordered,
+        start: None,
+        loose: false,
+        items: vec![item("Load film", true), item("Develop", false)],
+    });
+    assert_eq!(
+        render_block(&list),
+        "<ul>
+  <li><label><input type=\"checkbox\" disabled checked /> Load film</label></li>
+  <li><label><input type=\"checkbox\" disabled /> Develop</label></li>
+</ul>"
+    );
+}
+
+#[test]
+pub fn test_render_table() {
+    let table = Block::Table(Table {
+        head: vec![TableRow {
+            cells: vec![vec![text("Date")], vec![text("Weather")]],
+        }],
+        body: vec![TableRow {
+            cells: vec![vec![text("1 January")], vec![text("Sunny")]],
+        }],
+        ..Default::default()
+    });
+    assert_eq!(
+        render_block(&table),
+        "<table>
+  <thead>
+    <tr>
+      <th scope=\"col\">Date</th>
+      <th scope=\"col\">Weather</th>
+    </tr>
+  </thead>
+  <tbody>
+    <tr>
+      <td>1 January</td>
+      <td>Sunny</td>
+    </tr>
+  </tbody>
+</table>"
+    );
+}
+
+#[test]
+pub fn test_render_table_alignments() {
+    let table = Block::Table(Table {
+        alignments: vec![Some(TableAlign::Left), Some(TableAlign::Right)],
+        head: vec![TableRow {
+            cells: vec![vec![text("Film")], vec![text("ISO")]],
+        }],
+        body: vec![TableRow {
+            cells: vec![
+                vec![Inline::Code(String::from("HP5"))],
+                vec![text("400")],
+                vec![text("Extra")],
+            ],
+        }],
+    });
+    assert_eq!(
+        render_block(&table),
+        "<table>
+  <thead>
+    <tr>
+      <th scope=\"col\" style=\"text-align: left\">Film</th>
+      <th scope=\"col\" style=\"text-align: right\">ISO</th>
+    </tr>
+  </thead>
+  <tbody>
+    <tr>
+      <td style=\"text-align: left\"><InlineCodeFragment code={`HP5`} /></td>
+      <td style=\"text-align: right\">400</td>
+      <td>Extra</td>
+    </tr>
+  </tbody>
+</table>"
+    );
+}
+
+#[test]
+pub fn test_render_footnotes() {
+    let footnotes = vec![Footnote {
+        children: vec![Block::Paragraph(vec![text("Mostly 35 mm.")])],
+        references: 2,
+    }];
+    assert_eq!(
+        render_footnotes(&footnotes),
+        "<section class=\"footnotes\" aria-labelledby=\"footnote-label\">
+<h2 id=\"footnote-label\" class=\"sr-only\">Footnotes</h2>
+<ol>
+  <li id=\"fn-1\">
+<p>Mostly 35 mm. <a href=\"#fnref-1\" class=\"footnote-back-reference\" aria-label=\"Back to reference 1\">↩</a> <a href=\"#fnref-1-2\" class=\"footnote-back-reference\" aria-label=\"Back to reference 1-2\">↩</a></p>
+  </li>
+</ol>
+</section>"
+    );
+
+    let inlines = vec![
+        Inline::FootnoteReference(FootnoteReference {
+            number: 1,
+            occurrence: 2,
+            sidenote: None,
+        }),
+        Inline::FootnoteReference(FootnoteReference {
+            number: 1,
+            occurrence: 1,
+            sidenote: Some(vec![Block::Paragraph(vec![text("Mostly 35 mm.")])]),
+        }),
+    ];
+    assert_eq!(
+        render_inlines(&inlines),
+        "<sup><a href=\"#fn-1\" id=\"fnref-1-2\" aria-describedby=\"footnote-label\">1</a></sup><Sidenote number={1}>Mostly 35 mm.</Sidenote>"
+    );
+
+    // sidenote paragraphs stay inline, so the referencing paragraph is not broken apart
+    let paragraph = Block::Paragraph(vec![
+        text("Text"),
+        Inline::FootnoteReference(FootnoteReference {
+            number: 1,
+            occurrence: 1,
+            sidenote: Some(vec![
+                Block::Paragraph(vec![text("One.")]),
+                Block::Paragraph(vec![text("Two.")]),
+            ]),
+        }),
+        text("."),
+    ]);
+    assert_eq!(
+        render_block(&paragraph),
+        "<p>Text<Sidenote number={1}>One.<br />Two.</Sidenote>.</p>"
+    );
+
+    // a definition left in the blocks renders nothing, rather than panicking
+    let definition = Block::FootnoteDefinition(FootnoteDefinition {
+        label: String::from("^film"),
+        children: vec![Block::Paragraph(vec![text("Mostly 35 mm.")])],
+    });
+    assert_eq!(render_block(&definition), "");
+}
+
+#[test]
+pub fn test_render_frontmatter_value() {
+    let value = FrontmatterValue::Map(vec![
+        (
+            String::from("title"),
+            FrontmatterValue::String(String::from("NewTech's first year")),
+        ),
+        (
+            String::from("tags"),
+            FrontmatterValue::List(vec![
+                FrontmatterValue::String(String::from("rust")),
+                FrontmatterValue::String(String::from("astro")),
+            ]),
+        ),
+        (String::from("draft"), FrontmatterValue::Boolean(false)),
+        (
+            String::from("rating"),
+            FrontmatterValue::Real(String::from("4.5")),
+        ),
+        (
+            String::from("og:image"),
+            FrontmatterValue::Map(vec![(
+                String::from("width"),
+                FrontmatterValue::Integer(1200),
+            )]),
+        ),
+        (String::from("canonical"), FrontmatterValue::Null),
+    ]);
+    assert_eq!(
+        render_frontmatter_value(&value, 0),
+        "{
+  title: 'NewTech\\'s first year',
+  tags: ['rust', 'astro'],
+  draft: false,
+  rating: 4.5,
+  'og:image': {
+    width: 1200,
+  },
+  canonical: null,
+}"
+    );
+}
@@ -13,6 +13,10 @@ impl<T> Stack<T> {
         self.structure.last()
     }
 
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.structure.last_mut()
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         self.structure.pop()
     }
@@ -21,8 +25,9 @@ impl<T> Stack<T> {
         self.structure.push(element);
     }
 
-    pub fn len(&self) -> usize {
-        self.structure.len()
+    // elements from the bottom of the stack to the top
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.structure.iter()
     }
 
     pub fn is_empty(&self) -> bool {